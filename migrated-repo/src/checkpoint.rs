@@ -0,0 +1,136 @@
+//! Binary checkpoints of the streaming states
+//!
+//! A checkpoint is the magic `XXHC`, the format version, an algorithm tag and
+//! then the state fields, all integers little-endian, and last the XXH64 of
+//! everything before it. A checkpoint whose checksum does not match is
+//! rejected. The layout of a given version never changes, so checkpoints can
+//! be restored by later releases and on other hosts. Writing a checkpoint
+//! needs the `alloc` feature; restoring one does not.
+
+use crate::error::{XXHashError, XXHashResult};
+use crate::xxh64::xxh64;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// Leading bytes of every checkpoint
+pub const CHECKPOINT_MAGIC: [u8; 4] = *b"XXHC";
+
+/// Current checkpoint format version
+pub const CHECKPOINT_VERSION: u8 = 1;
+
+/// Algorithm tags stored after the version byte
+pub(crate) const TAG_XXH32: u8 = 1;
+pub(crate) const TAG_XXH64: u8 = 2;
+pub(crate) const TAG_XXH3: u8 = 3;
+
+/// Builds a checkpoint
+#[cfg(feature = "alloc")]
+pub(crate) struct CheckpointWriter {
+    out: Vec<u8>,
+}
+
+#[cfg(feature = "alloc")]
+impl CheckpointWriter {
+    pub(crate) fn new(tag: u8) -> Self {
+        let mut out = Vec::with_capacity(64);
+        out.extend_from_slice(&CHECKPOINT_MAGIC);
+        out.push(CHECKPOINT_VERSION);
+        out.push(tag);
+        Self { out }
+    }
+
+    pub(crate) fn put_u8(&mut self, value: u8) {
+        self.out.push(value);
+    }
+
+    pub(crate) fn put_bool(&mut self, value: bool) {
+        self.out.push(value as u8);
+    }
+
+    pub(crate) fn put_u32(&mut self, value: u32) {
+        self.out.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub(crate) fn put_u64(&mut self, value: u64) {
+        self.out.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub(crate) fn put_bytes(&mut self, bytes: &[u8]) {
+        self.out.extend_from_slice(bytes);
+    }
+
+    /// Append the checksum
+    pub(crate) fn finish(mut self) -> Vec<u8> {
+        let checksum = xxh64(&self.out);
+        self.put_u64(checksum);
+        self.out
+    }
+}
+
+/// Parses a checkpoint; every malformed input is `XXHashError::InvalidState`
+pub(crate) struct CheckpointReader<'a> {
+    input: &'a [u8],
+}
+
+impl<'a> CheckpointReader<'a> {
+    /// Check the checksum and header and position the reader on the state fields
+    pub(crate) fn new(input: &'a [u8], tag: u8) -> XXHashResult<Self> {
+        let payload_len = input.len().checked_sub(8).ok_or(XXHashError::InvalidState)?;
+        let (payload, checksum) = input.split_at(payload_len);
+        if xxh64(payload).to_le_bytes() != checksum {
+            return Err(XXHashError::InvalidState);
+        }
+        let mut reader = Self { input: payload };
+        if reader.get_bytes(4)? != CHECKPOINT_MAGIC
+            || reader.get_u8()? != CHECKPOINT_VERSION
+            || reader.get_u8()? != tag
+        {
+            return Err(XXHashError::InvalidState);
+        }
+        Ok(reader)
+    }
+
+    pub(crate) fn get_bytes(&mut self, len: usize) -> XXHashResult<&'a [u8]> {
+        if self.input.len() < len {
+            return Err(XXHashError::InvalidState);
+        }
+        let (bytes, rest) = self.input.split_at(len);
+        self.input = rest;
+        Ok(bytes)
+    }
+
+    pub(crate) fn get_array<const N: usize>(&mut self) -> XXHashResult<[u8; N]> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.get_bytes(N)?);
+        Ok(array)
+    }
+
+    pub(crate) fn get_u8(&mut self) -> XXHashResult<u8> {
+        Ok(self.get_bytes(1)?[0])
+    }
+
+    pub(crate) fn get_bool(&mut self) -> XXHashResult<bool> {
+        match self.get_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(XXHashError::InvalidState),
+        }
+    }
+
+    pub(crate) fn get_u32(&mut self) -> XXHashResult<u32> {
+        Ok(u32::from_le_bytes(self.get_array()?))
+    }
+
+    pub(crate) fn get_u64(&mut self) -> XXHashResult<u64> {
+        Ok(u64::from_le_bytes(self.get_array()?))
+    }
+
+    /// Reject trailing bytes before the checksum
+    pub(crate) fn finish(self) -> XXHashResult<()> {
+        if self.input.is_empty() {
+            Ok(())
+        } else {
+            Err(XXHashError::InvalidState)
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! `const fn` versions of XXH32, XXH64 and XXH3_64
+//!
+//! These evaluate at compile time, in `const` items and `match` patterns, for
+//! inputs of any length. The round, mixing and avalanche steps are the
+//! runtime `const fn` helpers; only the loops and the reads at an offset live
+//! here, since iterators, range indexing and the SIMD backends are not
+//! available in `const` contexts. At run time they are slower than the
+//! regular functions and only meant for constants. Inputs of a few hundred
+//! KiB and more run into the compiler's `long_running_const_eval` lint.
+
+use crate::constants::*;
+use crate::xxh32::{xxh32_init_lanes, xxh32_merge_lanes, xxh32_mix_byte, xxh32_mix_word, xxh32_round};
+use crate::xxh3::{generate_secret_from_seed, xxh3_len_0to16_64b, xxh3_merge_accs, xxh3_mix16b};
+use crate::xxh3_backend::scalar::{accumulate_lane, scramble_lane};
+use crate::xxh64::{xxh64_init_lanes, xxh64_merge_lanes, xxh64_mix_byte, xxh64_mix_half, xxh64_mix_word, xxh64_round};
+
+/// Little-endian loads at an offset
+const fn read32(data: &[u8], at: usize) -> u32 {
+    read_u32_le(data.split_at(at).1)
+}
+
+const fn read64(data: &[u8], at: usize) -> u64 {
+    read_u64_le(data.split_at(at).1)
+}
+
+/// `xxh3_mix16b` of the 16 bytes at `at` with the secret at `secret_at`
+const fn mix16b(data: &[u8], at: usize, secret: &[u8], secret_at: usize, seed: u64) -> u64 {
+    xxh3_mix16b(data.split_at(at).1, secret.split_at(secret_at).1, seed)
+}
+
+// XXH32
+
+/// XXH32 of `data`, equal to `xxh32_with_seed(data, seed)`
+pub const fn xxh32_const(data: &[u8], seed: u32) -> u32 {
+    let len = data.len();
+    let mut at = 0;
+    let mut h;
+
+    if len >= 16 {
+        let mut v = xxh32_init_lanes(seed);
+        while at + 16 <= len {
+            let mut i = 0;
+            while i < 4 {
+                v[i] = xxh32_round(v[i], read32(data, at + 4 * i));
+                i += 1;
+            }
+            at += 16;
+        }
+        h = xxh32_merge_lanes(&v);
+    } else {
+        h = seed.wrapping_add(XXH32_PRIME5);
+    }
+    h = h.wrapping_add(len as u32);
+
+    while at + 4 <= len {
+        h = xxh32_mix_word(h, read32(data, at));
+        at += 4;
+    }
+    while at < len {
+        h = xxh32_mix_byte(h, data[at]);
+        at += 1;
+    }
+
+    xxh32_avalanche(h)
+}
+
+// XXH64
+
+/// XXH64 of `data`, equal to `xxh64_with_seed(data, seed)`
+pub const fn xxh64_const(data: &[u8], seed: u64) -> u64 {
+    let len = data.len();
+    let mut at = 0;
+    let mut h;
+
+    if len >= 32 {
+        let mut v = xxh64_init_lanes(seed);
+        while at + 32 <= len {
+            let mut i = 0;
+            while i < 4 {
+                v[i] = xxh64_round(v[i], read64(data, at + 8 * i));
+                i += 1;
+            }
+            at += 32;
+        }
+        h = xxh64_merge_lanes(&v);
+    } else {
+        h = seed.wrapping_add(XXH64_PRIME5);
+    }
+    h = h.wrapping_add(len as u64);
+
+    while at + 8 <= len {
+        h = xxh64_mix_word(h, read64(data, at));
+        at += 8;
+    }
+    if at + 4 <= len {
+        h = xxh64_mix_half(h, read32(data, at));
+        at += 4;
+    }
+    while at < len {
+        h = xxh64_mix_byte(h, data[at]);
+        at += 1;
+    }
+
+    xxh64_avalanche(h)
+}
+
+// XXH3_64
+
+/// XXH3_64 of `data`, equal to `xxh3_64bits_with_seed(data, seed)`
+pub const fn xxh3_64bits_const(data: &[u8], seed: u64) -> u64 {
+    let len = data.len();
+    let secret = &XXH3_DEFAULT_SECRET;
+
+    if len <= 16 {
+        xxh3_len_0to16_64b(data, secret, seed)
+    } else if len <= 128 {
+        let mut acc = (len as u64).wrapping_mul(XXH64_PRIME1);
+        let mut i = 0;
+        while i <= (len - 1) / 32 {
+            acc = acc.wrapping_add(mix16b(data, 16 * i, secret, 32 * i, seed));
+            acc = acc.wrapping_add(mix16b(data, len - 16 * (i + 1), secret, 32 * i + 16, seed));
+            i += 1;
+        }
+        xxh3_avalanche(acc)
+    } else if len <= XXH3_MIDSIZE_MAX {
+        let mut acc = (len as u64).wrapping_mul(XXH64_PRIME1);
+        let mut i = 0;
+        while i < 8 {
+            acc = acc.wrapping_add(mix16b(data, 16 * i, secret, 16 * i, seed));
+            i += 1;
+        }
+        let mut acc_end = mix16b(data, len - 16, secret, XXH3_SECRET_SIZE_MIN - XXH3_MIDSIZE_LASTOFFSET, seed);
+        acc = xxh3_avalanche(acc);
+        while i < len / 16 {
+            acc_end = acc_end.wrapping_add(mix16b(data, 16 * i, secret, 16 * (i - 8) + XXH3_MIDSIZE_STARTOFFSET, seed));
+            i += 1;
+        }
+        xxh3_avalanche(acc.wrapping_add(acc_end))
+    } else if seed == 0 {
+        hashlong_64b(data, secret)
+    } else {
+        hashlong_64b(data, &generate_secret_from_seed(seed))
+    }
+}
+
+const fn accumulate_512(acc: &mut [u64; XXH3_ACC_NB], data: &[u8], at: usize, secret: &[u8], secret_at: usize) {
+    let mut i = 0;
+    while i < XXH3_ACC_NB {
+        accumulate_lane(acc, i, read64(data, at + 8 * i), read64(secret, secret_at + 8 * i));
+        i += 1;
+    }
+}
+
+const fn scramble(acc: &mut [u64; XXH3_ACC_NB], secret: &[u8], secret_at: usize) {
+    let mut i = 0;
+    while i < XXH3_ACC_NB {
+        acc[i] = scramble_lane(acc[i], read64(secret, secret_at + 8 * i));
+        i += 1;
+    }
+}
+
+const fn hashlong_64b(data: &[u8], secret: &[u8; XXH3_SECRET_DEFAULT_SIZE]) -> u64 {
+    let len = data.len();
+    let nb_stripes_per_block = (XXH3_SECRET_DEFAULT_SIZE - XXH3_STRIPE_LEN) / XXH3_SECRET_CONSUME_RATE;
+    let block_len = XXH3_STRIPE_LEN * nb_stripes_per_block;
+    let nb_blocks = (len - 1) / block_len;
+    let mut acc = XXH3_INIT_ACC;
+
+    let mut n = 0;
+    while n < nb_blocks {
+        let mut s = 0;
+        while s < nb_stripes_per_block {
+            accumulate_512(&mut acc, data, n * block_len + s * XXH3_STRIPE_LEN, secret, s * XXH3_SECRET_CONSUME_RATE);
+            s += 1;
+        }
+        scramble(&mut acc, secret, XXH3_SECRET_DEFAULT_SIZE - XXH3_STRIPE_LEN);
+        n += 1;
+    }
+
+    // Last partial block, then the last stripe, which may overlap it
+    let nb_stripes = ((len - 1) - block_len * nb_blocks) / XXH3_STRIPE_LEN;
+    let mut s = 0;
+    while s < nb_stripes {
+        accumulate_512(&mut acc, data, nb_blocks * block_len + s * XXH3_STRIPE_LEN, secret, s * XXH3_SECRET_CONSUME_RATE);
+        s += 1;
+    }
+    accumulate_512(
+        &mut acc,
+        data,
+        len - XXH3_STRIPE_LEN,
+        secret,
+        XXH3_SECRET_DEFAULT_SIZE - XXH3_STRIPE_LEN - XXH3_SECRET_LASTACC_START,
+    );
+
+    xxh3_merge_accs(
+        &acc,
+        secret.split_at(XXH3_SECRET_MERGEACCS_START).1,
+        (len as u64).wrapping_mul(XXH64_PRIME1),
+    )
+}
@@ -20,6 +20,28 @@ pub const XXH3_SECRET_DEFAULT_SIZE: usize = 192;
 pub const XXH3_MIDSIZE_MAX: usize = 240;
 pub const XXH3_MIDSIZE_STARTOFFSET: usize = 3;
 pub const XXH3_MIDSIZE_LASTOFFSET: usize = 17;
+// Custom secrets up to this size are stored inline in XXH3State
+pub const XXH3_SECRET_INLINE_MAX: usize = 256;
+
+// XXH3 long input constants
+pub const XXH3_STRIPE_LEN: usize = 64;
+pub const XXH3_SECRET_CONSUME_RATE: usize = 8;
+pub const XXH3_ACC_NB: usize = 8;
+pub const XXH3_SECRET_LASTACC_START: usize = 7;
+pub const XXH3_SECRET_MERGEACCS_START: usize = 11;
+pub const XXH3_INTERNALBUFFER_SIZE: usize = 256;
+
+// Initial accumulator values for long inputs
+pub const XXH3_INIT_ACC: [u64; XXH3_ACC_NB] = [
+    XXH32_PRIME3 as u64,
+    XXH64_PRIME1,
+    XXH64_PRIME2,
+    XXH64_PRIME3,
+    XXH64_PRIME4,
+    XXH32_PRIME2 as u64,
+    XXH64_PRIME5,
+    XXH32_PRIME1 as u64,
+];
 
 // XXH3 mixing constants from C source
 pub const PRIME_MX1: u64 = 0x165667919E3779F9;
@@ -44,38 +66,62 @@ pub const XXH3_DEFAULT_SECRET: [u8; XXH3_SECRET_DEFAULT_SIZE] = [
 // Bit manipulation helpers
 #[inline]
 pub const fn rotl32(x: u32, r: u32) -> u32 {
-    (x << r) | (x >> (32 - r))
+    x.rotate_left(r)
 }
 
 #[inline] 
 pub const fn rotl64(x: u64, r: u32) -> u64 {
-    (x << r) | (x >> (64 - r))
+    x.rotate_left(r)
 }
 
-#[inline]
+// Little/big-endian loads: one length check, then a single unaligned load
+
+#[inline(always)]
 pub const fn read_u32_le(data: &[u8]) -> u32 {
-    u32::from_le_bytes([data[0], data[1], data[2], data[3]])
+    match data.first_chunk::<4>() {
+        Some(bytes) => u32::from_le_bytes(*bytes),
+        None => panic!("read_u32_le: fewer than 4 bytes"),
+    }
 }
 
-#[inline]
+#[inline(always)]
 pub const fn read_u64_le(data: &[u8]) -> u64 {
-    u64::from_le_bytes([
-        data[0], data[1], data[2], data[3],
-        data[4], data[5], data[6], data[7],
-    ])
+    match data.first_chunk::<8>() {
+        Some(bytes) => u64::from_le_bytes(*bytes),
+        None => panic!("read_u64_le: fewer than 8 bytes"),
+    }
 }
 
-#[inline]
+#[inline(always)]
+pub const fn read_u64_be(data: &[u8]) -> u64 {
+    match data.first_chunk::<8>() {
+        Some(bytes) => u64::from_be_bytes(*bytes),
+        None => panic!("read_u64_be: fewer than 8 bytes"),
+    }
+}
+
+#[inline(always)]
 pub const fn read_u128_le(data: &[u8]) -> u128 {
-    u128::from_le_bytes([
-        data[0], data[1], data[2], data[3], data[4], data[5], data[6], data[7],
-        data[8], data[9], data[10], data[11], data[12], data[13], data[14], data[15],
-    ])
+    match data.first_chunk::<16>() {
+        Some(bytes) => u128::from_le_bytes(*bytes),
+        None => panic!("read_u128_le: fewer than 16 bytes"),
+    }
+}
+
+// XXH32 avalanche function
+#[inline]
+pub const fn xxh32_avalanche(mut hash: u32) -> u32 {
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(XXH32_PRIME2);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(XXH32_PRIME3);
+    hash ^= hash >> 16;
+    hash
 }
 
 // XXH64 avalanche function - different from XXH3_avalanche!
 #[inline]
-pub fn xxh64_avalanche(mut hash: u64) -> u64 {
+pub const fn xxh64_avalanche(mut hash: u64) -> u64 {
     hash ^= hash >> 33;
     hash = hash.wrapping_mul(XXH64_PRIME2);
     hash ^= hash >> 29;
@@ -86,7 +132,7 @@ pub fn xxh64_avalanche(mut hash: u64) -> u64 {
 
 // XXH3 avalanche function
 #[inline]
-pub fn xxh3_avalanche(mut h: u64) -> u64 {
+pub const fn xxh3_avalanche(mut h: u64) -> u64 {
     h ^= h >> 37;
     h = h.wrapping_mul(PRIME_MX1);
     h ^= h >> 32;
@@ -1,6 +1,17 @@
 //! Error handling for xxHash operations
 
-use std::fmt;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::path::PathBuf;
+
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
+
+#[cfg(feature = "alloc")]
+use crate::algorithm::Algorithm;
+use crate::xxh3_backend::XXH3Backend;
 
 /// Result type for xxHash operations
 pub type XXHashResult<T> = Result<T, XXHashError>;
@@ -19,7 +30,25 @@ pub enum XXHashError {
     /// Invalid seed value
     InvalidSeed,
     /// Operation failed
+    #[cfg(feature = "alloc")]
     OperationFailed(String),
+    /// Malformed hex representation of a hash
+    #[cfg(feature = "alloc")]
+    InvalidHexDigest(String),
+    /// Unrecognised algorithm name
+    #[cfg(feature = "alloc")]
+    UnknownAlgorithm(String),
+    /// I/O failure while reading input, with the file path when there is one
+    #[cfg(feature = "std")]
+    Io { kind: io::ErrorKind, path: Option<PathBuf> },
+    /// Data did not hash to the expected canonical digest
+    #[cfg(feature = "alloc")]
+    ChecksumMismatch { expected: Vec<u8>, actual: Vec<u8> },
+    /// A secret was given for an algorithm that has no secret
+    #[cfg(feature = "alloc")]
+    SecretNotSupported(Algorithm),
+    /// The CPU does not support the requested XXH3 backend
+    BackendUnavailable(XXH3Backend),
 }
 
 impl fmt::Display for XXHashError {
@@ -28,9 +57,14 @@ impl fmt::Display for XXHashError {
             XXHashError::InvalidInputLength(len) => {
                 write!(f, "Invalid input length: {}", len)
             }
+            #[cfg(feature = "alloc")]
             XXHashError::InvalidSecretSize(size) => {
                 write!(f, "Invalid secret size: {} (must be >= 136 bytes)", size)
             }
+            #[cfg(not(feature = "alloc"))]
+            XXHashError::InvalidSecretSize(size) => {
+                write!(f, "Invalid secret size: {} (must be >= 136 bytes, and <= 256 bytes for streaming states without alloc)", size)
+            }
             XXHashError::InvalidState => {
                 write!(f, "Invalid hash state - corrupted or uninitialized")
             }
@@ -40,14 +74,57 @@ impl fmt::Display for XXHashError {
             XXHashError::InvalidSeed => {
                 write!(f, "Invalid seed value")
             }
+            #[cfg(feature = "alloc")]
             XXHashError::OperationFailed(msg) => {
                 write!(f, "Operation failed: {}", msg)
             }
+            #[cfg(feature = "alloc")]
+            XXHashError::InvalidHexDigest(text) => {
+                write!(f, "Invalid hex digest: '{}'", text)
+            }
+            #[cfg(feature = "alloc")]
+            XXHashError::UnknownAlgorithm(name) => {
+                write!(f, "Unknown hash algorithm: '{}'", name)
+            }
+            #[cfg(feature = "std")]
+            XXHashError::Io { kind, path: Some(path) } => {
+                write!(f, "I/O error on '{}': {}", path.display(), kind)
+            }
+            #[cfg(feature = "std")]
+            XXHashError::Io { kind, path: None } => {
+                write!(f, "I/O error: {}", kind)
+            }
+            #[cfg(feature = "alloc")]
+            XXHashError::ChecksumMismatch { expected, actual } => {
+                write!(f, "Checksum mismatch: expected ")?;
+                write_hex(f, expected)?;
+                write!(f, ", got ")?;
+                write_hex(f, actual)
+            }
+            #[cfg(feature = "alloc")]
+            XXHashError::SecretNotSupported(algorithm) => {
+                write!(f, "{} does not take a secret", algorithm)
+            }
+            XXHashError::BackendUnavailable(backend) => {
+                write!(f, "{:?} is not supported on this CPU", backend)
+            }
         }
     }
 }
 
-impl std::error::Error for XXHashError {}
+#[cfg(feature = "alloc")]
+fn write_hex(f: &mut fmt::Formatter<'_>, bytes: &[u8]) -> fmt::Result {
+    bytes.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+}
+
+impl core::error::Error for XXHashError {}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for XXHashError {
+    fn from(err: io::Error) -> Self {
+        XXHashError::Io { kind: err.kind(), path: None }
+    }
+}
 
 /// Convert XXHashError to a result indicating success (0) or error (1)
 impl XXHashError {
@@ -58,7 +135,19 @@ impl XXHashError {
             XXHashError::InvalidState => 3,
             XXHashError::BufferTooSmall { .. } => 4,
             XXHashError::InvalidSeed => 5,
+            #[cfg(feature = "alloc")]
             XXHashError::OperationFailed(_) => 6,
+            #[cfg(feature = "alloc")]
+            XXHashError::InvalidHexDigest(_) => 7,
+            #[cfg(feature = "alloc")]
+            XXHashError::UnknownAlgorithm(_) => 8,
+            #[cfg(feature = "std")]
+            XXHashError::Io { .. } => 9,
+            #[cfg(feature = "alloc")]
+            XXHashError::ChecksumMismatch { .. } => 10,
+            #[cfg(feature = "alloc")]
+            XXHashError::SecretNotSupported(_) => 11,
+            XXHashError::BackendUnavailable(_) => 12,
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,469 @@
+//! `std::hash::Hasher` and `BuildHasher` integration for HashMap/HashSet
+
+use core::hash::{BuildHasher, Hasher};
+#[cfg(feature = "std")]
+use std::collections::hash_map::RandomState;
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::Read;
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
+
+use crate::constants::{XXH3_MIDSIZE_MAX, XXH3_SECRET_DEFAULT_SIZE};
+use crate::xxh3::{generate_secret_from_seed, xxh3_64_u64_with_seed, xxh3_64bits_with_seed, XXH3State};
+use crate::xxh32::{xxh32_with_seed, XXH32State};
+use crate::xxh64::{xxh64_with_seed, XXH64State};
+
+/// XXH32-backed hasher
+///
+/// `u64` and `usize` values are hashed as little-endian 64-bit words. A
+/// single integer key never touches the streaming state and is hashed in
+/// one shot by `finish`.
+#[derive(Debug, Clone)]
+pub struct XXH32Hasher {
+    state: XXH32State,
+    seed: u32,
+    pending: Option<u64>,
+    written: bool,
+}
+
+impl XXH32Hasher {
+    /// Create a hasher with the given seed
+    pub fn with_seed(seed: u32) -> Self {
+        Self {
+            state: XXH32State::new(seed),
+            seed,
+            pending: None,
+            written: false,
+        }
+    }
+
+    fn flush_pending(&mut self) {
+        if let Some(value) = self.pending.take() {
+            let _ = self.state.update(&value.to_le_bytes());
+        }
+    }
+}
+
+impl Default for XXH32Hasher {
+    fn default() -> Self {
+        Self::with_seed(0)
+    }
+}
+
+impl Hasher for XXH32Hasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.flush_pending();
+        self.written = true;
+        let _ = self.state.update(bytes);
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        if self.written || self.pending.is_some() {
+            self.write(&value.to_le_bytes());
+        } else {
+            self.pending = Some(value);
+        }
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.write_u64(value as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        match (self.written, self.pending) {
+            (false, Some(value)) => xxh32_with_seed(&value.to_le_bytes(), self.seed) as u64,
+            _ => self.state.digest() as u64,
+        }
+    }
+}
+
+/// XXH64-backed hasher, see `XXH32Hasher` for the integer fast path
+#[derive(Debug, Clone)]
+pub struct XXH64Hasher {
+    state: XXH64State,
+    seed: u64,
+    pending: Option<u64>,
+    written: bool,
+}
+
+impl XXH64Hasher {
+    /// Create a hasher with the given seed
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            state: XXH64State::new(seed),
+            seed,
+            pending: None,
+            written: false,
+        }
+    }
+
+    fn flush_pending(&mut self) {
+        if let Some(value) = self.pending.take() {
+            let _ = self.state.update(&value.to_le_bytes());
+        }
+    }
+}
+
+impl Default for XXH64Hasher {
+    fn default() -> Self {
+        Self::with_seed(0)
+    }
+}
+
+impl Hasher for XXH64Hasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.flush_pending();
+        self.written = true;
+        let _ = self.state.update(bytes);
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        if self.written || self.pending.is_some() {
+            self.write(&value.to_le_bytes());
+        } else {
+            self.pending = Some(value);
+        }
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.write_u64(value as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        match (self.written, self.pending) {
+            (false, Some(value)) => xxh64_with_seed(&value.to_le_bytes(), self.seed),
+            _ => self.state.digest(),
+        }
+    }
+}
+
+/// XXH3_64-backed hasher
+///
+/// A single `u64` or `usize` key is hashed as its little-endian bytes by
+/// `xxh3_64_u64_with_seed`. Other keys of up to 240 bytes, which covers
+/// almost every string key, are buffered inline and hashed in one shot by
+/// `finish`. Only longer input spills into a streaming state.
+#[derive(Debug, Clone)]
+pub struct XXH3Hasher {
+    seed: u64,
+    /// Secret derived from a non-zero seed, needed once the input spills
+    secret: Option<[u8; XXH3_SECRET_DEFAULT_SIZE]>,
+    buffer: [u8; XXH3_MIDSIZE_MAX],
+    buffered_size: usize,
+    state: Option<XXH3State>,
+    pending: Option<u64>,
+}
+
+impl XXH3Hasher {
+    /// Create a hasher with the given seed
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_derived_secret(seed, derived_secret(seed))
+    }
+
+    #[inline]
+    fn with_derived_secret(seed: u64, secret: Option<[u8; XXH3_SECRET_DEFAULT_SIZE]>) -> Self {
+        Self {
+            seed,
+            secret,
+            buffer: [0; XXH3_MIDSIZE_MAX],
+            buffered_size: 0,
+            state: None,
+            pending: None,
+        }
+    }
+
+    fn flush_pending(&mut self) {
+        if let Some(value) = self.pending.take() {
+            self.write(&value.to_le_bytes());
+        }
+    }
+
+    /// Move the buffered bytes into a streaming state
+    fn spill(&mut self) -> &mut XXH3State {
+        let mut state = match &self.secret {
+            Some(secret) => XXH3State::with_derived_secret(self.seed, secret),
+            None => XXH3State::new(),
+        };
+        let _ = state.update(&self.buffer[..self.buffered_size]);
+        self.state.insert(state)
+    }
+}
+
+impl Default for XXH3Hasher {
+    fn default() -> Self {
+        Self::with_seed(0)
+    }
+}
+
+impl Hasher for XXH3Hasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        self.flush_pending();
+        if let Some(state) = &mut self.state {
+            let _ = state.update(bytes);
+        } else if bytes.len() <= XXH3_MIDSIZE_MAX - self.buffered_size {
+            self.buffer[self.buffered_size..self.buffered_size + bytes.len()].copy_from_slice(bytes);
+            self.buffered_size += bytes.len();
+        } else {
+            let _ = self.spill().update(bytes);
+        }
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        if self.pending.is_some() || self.buffered_size > 0 || self.state.is_some() {
+            self.write(&value.to_le_bytes());
+        } else {
+            self.pending = Some(value);
+        }
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.write_u64(value as u64);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        match (&self.state, self.pending) {
+            (Some(state), _) => state.digest_64(),
+            (None, Some(value)) => xxh3_64_u64_with_seed(value, self.seed),
+            (None, None) => xxh3_64bits_with_seed(&self.buffer[..self.buffered_size], self.seed),
+        }
+    }
+}
+
+/// Secret used for long inputs with a non-zero seed
+fn derived_secret(seed: u64) -> Option<[u8; XXH3_SECRET_DEFAULT_SIZE]> {
+    (seed != 0).then(|| generate_secret_from_seed(seed))
+}
+
+/// Build XXH32 hashers with a fixed seed
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct XXH32BuildHasher {
+    pub seed: u32,
+}
+
+impl XXH32BuildHasher {
+    pub fn with_seed(seed: u32) -> Self {
+        Self { seed }
+    }
+}
+
+impl BuildHasher for XXH32BuildHasher {
+    type Hasher = XXH32Hasher;
+
+    fn build_hasher(&self) -> XXH32Hasher {
+        XXH32Hasher::with_seed(self.seed)
+    }
+}
+
+/// Build XXH64 hashers with a fixed seed
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct XXH64BuildHasher {
+    pub seed: u64,
+}
+
+impl XXH64BuildHasher {
+    pub fn with_seed(seed: u64) -> Self {
+        Self { seed }
+    }
+}
+
+impl BuildHasher for XXH64BuildHasher {
+    type Hasher = XXH64Hasher;
+
+    fn build_hasher(&self) -> XXH64Hasher {
+        XXH64Hasher::with_seed(self.seed)
+    }
+}
+
+/// Build XXH3 hashers with a fixed seed
+///
+/// The same key always hashes to the same value, across runs and hosts.
+/// The secret for a non-zero seed is derived once here and copied into each
+/// hasher, instead of being derived again for every key.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct XXH3BuildHasher {
+    seed: u64,
+    secret: Option<[u8; XXH3_SECRET_DEFAULT_SIZE]>,
+}
+
+impl XXH3BuildHasher {
+    pub fn with_seed(seed: u64) -> Self {
+        Self { seed, secret: derived_secret(seed) }
+    }
+
+    /// Seed of every hasher built
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl BuildHasher for XXH3BuildHasher {
+    type Hasher = XXH3Hasher;
+
+    #[inline]
+    fn build_hasher(&self) -> XXH3Hasher {
+        XXH3Hasher::with_derived_secret(self.seed, self.secret)
+    }
+}
+
+/// Build XXH3 hashers keyed with a random per-process seed
+///
+/// The seed is read once from the OS, so hash values cannot be predicted
+/// from outside the process. Use this for maps keyed by untrusted input.
+#[cfg(feature = "std")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RandomXXH3BuildHasher {
+    inner: XXH3BuildHasher,
+}
+
+#[cfg(feature = "std")]
+impl RandomXXH3BuildHasher {
+    pub fn new() -> Self {
+        Self { inner: XXH3BuildHasher::with_seed(process_seed()) }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for RandomXXH3BuildHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl BuildHasher for RandomXXH3BuildHasher {
+    type Hasher = XXH3Hasher;
+
+    fn build_hasher(&self) -> XXH3Hasher {
+        self.inner.build_hasher()
+    }
+}
+
+/// Per-process random seed, from /dev/urandom when available
+#[cfg(feature = "std")]
+fn process_seed() -> u64 {
+    static SEED: OnceLock<u64> = OnceLock::new();
+    *SEED.get_or_init(|| {
+        let mut bytes = [0u8; 8];
+        match File::open("/dev/urandom").and_then(|mut file| file.read_exact(&mut bytes)) {
+            Ok(()) => u64::from_le_bytes(bytes),
+            // Fall back to the OS-seeded keys of the standard library
+            Err(_) => RandomState::new().hash_one(0u64),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "std")]
+    use std::collections::{HashMap, HashSet};
+
+    #[test]
+    fn test_integer_fast_path_matches_bytes() {
+        let key = 0x0123_4567_89ab_cdefu64;
+        let seed = 0x9e3779b185ebca8d;
+
+        let mut hasher = XXH3Hasher::with_seed(seed);
+        hasher.write_u64(key);
+        assert_eq!(hasher.finish(), xxh3_64bits_with_seed(&key.to_le_bytes(), seed));
+
+        let mut hasher = XXH3BuildHasher::with_seed(seed).build_hasher();
+        hasher.write_usize(key as usize);
+        assert_eq!(hasher.finish(), xxh3_64_u64_with_seed(key, seed));
+
+        // An integer after other input goes through the buffer in little-endian order
+        let mut hasher = XXH3Hasher::with_seed(seed);
+        hasher.write_u64(1);
+        hasher.write_usize(key as usize);
+        let expected = [1u64.to_le_bytes(), key.to_le_bytes()].concat();
+        assert_eq!(hasher.finish(), xxh3_64bits_with_seed(&expected, seed));
+
+        let mut hasher = XXH64Hasher::with_seed(seed);
+        hasher.write_usize(key as usize);
+        assert_eq!(hasher.finish(), xxh64_with_seed(&key.to_le_bytes(), seed));
+
+        let mut hasher = XXH32Hasher::with_seed(7);
+        hasher.write_u64(key);
+        assert_eq!(hasher.finish(), xxh32_with_seed(&key.to_le_bytes(), 7) as u64);
+    }
+
+    #[test]
+    fn test_mixed_writes_match_concatenation() {
+        let mut expected = Vec::new();
+        expected.extend_from_slice(&42u64.to_le_bytes());
+        expected.extend_from_slice(b"key");
+        expected.extend_from_slice(&7u64.to_le_bytes());
+
+        let mut hasher = XXH3Hasher::with_seed(5);
+        hasher.write_u64(42);
+        hasher.write(b"key");
+        hasher.write_u64(7);
+        assert_eq!(hasher.finish(), xxh3_64bits_with_seed(&expected, 5));
+
+        let mut hasher = XXH64Hasher::with_seed(5);
+        hasher.write_u64(42);
+        hasher.write_u64(7);
+        assert_eq!(hasher.finish(), xxh64_with_seed(&[&42u64.to_le_bytes()[..], &7u64.to_le_bytes()].concat(), 5));
+
+        assert_eq!(XXH3Hasher::default().finish(), xxh3_64bits_with_seed(b"", 0));
+    }
+
+    #[test]
+    fn test_xxh3_spill_past_buffer() {
+        let data: Vec<u8> = (0..1000).map(|i| (i * 7) as u8).collect();
+        // Crossing 240 bytes mid-write, exactly at it, and in one big write
+        for seed in [0, 5] {
+            for split in [0, 100, 240, 241, 999] {
+                let build = XXH3BuildHasher::with_seed(seed);
+                let mut hasher = build.build_hasher();
+                hasher.write(&data[..split]);
+                hasher.write(&data[split..]);
+                assert_eq!(hasher.finish(), xxh3_64bits_with_seed(&data, seed), "seed {}, split {}", seed, split);
+            }
+            let mut hasher = XXH3Hasher::with_seed(seed);
+            hasher.write(&data[..240]);
+            assert_eq!(hasher.finish(), xxh3_64bits_with_seed(&data[..240], seed));
+        }
+    }
+
+    #[test]
+    fn test_fixed_seed_is_deterministic() {
+        let build = XXH3BuildHasher::with_seed(1234);
+        assert_eq!(build.hash_one("metric.name"), XXH3BuildHasher::with_seed(1234).hash_one("metric.name"));
+        assert_ne!(build.hash_one("metric.name"), XXH3BuildHasher::with_seed(1235).hash_one("metric.name"));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_hash_collections() {
+        let mut map: HashMap<u64, &str, XXH3BuildHasher> = HashMap::default();
+        map.insert(1, "one");
+        map.insert(2, "two");
+        assert_eq!(map.get(&1), Some(&"one"));
+
+        let mut set: HashSet<String, XXH64BuildHasher> = HashSet::with_hasher(XXH64BuildHasher::with_seed(9));
+        set.insert("alpha".to_string());
+        assert!(set.contains("alpha"));
+
+        let mut set: HashSet<&str, XXH32BuildHasher> = HashSet::default();
+        set.insert("beta");
+        assert!(set.contains("beta"));
+
+        let mut map: HashMap<String, u32, RandomXXH3BuildHasher> = HashMap::default();
+        map.insert("gamma".to_string(), 3);
+        assert_eq!(map["gamma"], 3);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_random_seed_is_per_process() {
+        let a = RandomXXH3BuildHasher::new();
+        let b = RandomXXH3BuildHasher::default();
+        assert_eq!(a, b);
+        assert_eq!(a.hash_one(99u64), b.hash_one(99u64));
+    }
+}
@@ -0,0 +1,595 @@
+//! `std::io` integration: `Write` for the streaming states, reader hashing
+//! and pass-through hashing and verifying adapters
+
+use std::fs::File;
+use std::io::{self, BufRead, Read, Write};
+use std::path::Path;
+
+use crate::algorithm::{Algorithm, StreamingHash, XXH128Stream, XXH3_64Stream};
+use crate::error::{XXHashError, XXHashResult};
+use crate::xxh3::XXH3State;
+use crate::xxh32::XXH32State;
+use crate::xxh64::XXH64State;
+
+/// Read size used when hashing readers
+///
+/// Large enough to amortise the syscall per read, small enough to stay in
+/// L2 cache while it is hashed; same block size as xxhsum.
+pub const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+fn to_io_error(err: XXHashError) -> io::Error {
+    io::Error::other(err)
+}
+
+/// `Write` for states whose `update` takes the whole slice
+macro_rules! impl_write {
+    ($($state:ty),*) => {$(
+        impl Write for $state {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.update(buf).map_err(to_io_error)?;
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+    )*};
+}
+
+impl_write!(XXH32State, XXH64State, XXH3State, XXH3_64Stream, XXH128Stream, dyn StreamingHash + Send);
+
+/// Feed everything from `reader` into `state`, returning the number of bytes read
+pub fn hash_reader_into<R: Read + ?Sized>(reader: &mut R, state: &mut dyn StreamingHash) -> XXHashResult<u64> {
+    let mut buffer = vec![0u8; READ_BUFFER_SIZE];
+    let mut total = 0u64;
+    loop {
+        let n = match reader.read(&mut buffer) {
+            Ok(0) => return Ok(total),
+            Ok(n) => n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err.into()),
+        };
+        state.update(&buffer[..n])?;
+        total += n as u64;
+    }
+}
+
+/// Hash everything from `reader` with `algorithm`, returning the canonical digest
+pub fn hash_reader<R: Read>(algorithm: Algorithm, mut reader: R) -> XXHashResult<Vec<u8>> {
+    let mut state = algorithm.hasher();
+    hash_reader_into(&mut reader, state.as_mut())?;
+    Ok(state.digest_bytes())
+}
+
+/// Hash the contents of the file at `path`, returning the canonical digest
+///
+/// Open and read failures are reported as `XXHashError::Io` with the path.
+pub fn hash_file<P: AsRef<Path>>(algorithm: Algorithm, path: P) -> XXHashResult<Vec<u8>> {
+    let path = path.as_ref();
+    let with_path = |err: io::Error| XXHashError::Io { kind: err.kind(), path: Some(path.to_path_buf()) };
+    let file = File::open(path).map_err(with_path)?;
+    hash_reader(algorithm, file).map_err(|err| match err {
+        XXHashError::Io { kind, path: None } => XXHashError::Io { kind, path: Some(path.to_path_buf()) },
+        other => other,
+    })
+}
+
+/// Reader adapter that hashes every byte read through it
+///
+/// Bytes are hashed as they are handed to the caller, so short reads,
+/// `read_exact` and `BufRead::consume` all hash exactly the data returned.
+/// If `consume` cannot hash the bytes it drops, the failure is kept and
+/// returned by every later `read`, `fill_buf` and `digest_bytes`.
+#[derive(Debug)]
+pub struct HashingReader<R, H> {
+    inner: R,
+    hasher: H,
+    bytes: u64,
+    /// Unconsumed length of the slice returned by the last `fill_buf`
+    filled: usize,
+    /// Failure in `consume`, which cannot return it
+    error: Option<XXHashError>,
+}
+
+impl<R, H: StreamingHash> HashingReader<R, H> {
+    /// Wrap `inner`, feeding everything read into `hasher`
+    pub fn new(inner: R, hasher: H) -> Self {
+        Self { inner, hasher, bytes: 0, filled: 0, error: None }
+    }
+
+    /// Number of bytes read so far
+    pub fn byte_count(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Canonical digest of the bytes read so far, or the error that made
+    /// `consume` skip some of them
+    pub fn digest_bytes(&self) -> XXHashResult<Vec<u8>> {
+        match &self.error {
+            Some(err) => Err(err.clone()),
+            None => Ok(self.hasher.digest_bytes()),
+        }
+    }
+
+    /// Streaming state, for typed digests; does not report `consume` failures
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Mutable access to the wrapped reader; bytes read through it directly
+    /// are not hashed
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> (R, H) {
+        (self.inner, self.hasher)
+    }
+
+    /// `fill_buf` that also lends the hasher, so callers can check it at EOF
+    fn fill(&mut self) -> io::Result<(&[u8], &H)>
+    where
+        R: BufRead,
+    {
+        self.check()?;
+        let buf = self.inner.fill_buf()?;
+        self.filled = buf.len();
+        Ok((buf, &self.hasher))
+    }
+
+    /// Fail if an earlier `consume` lost data
+    fn check(&self) -> io::Result<()> {
+        match &self.error {
+            None => Ok(()),
+            Some(XXHashError::Io { kind, .. }) => Err(io::Error::from(*kind)),
+            Some(err) => Err(to_io_error(err.clone())),
+        }
+    }
+
+    fn hash(&mut self, data: &[u8]) -> io::Result<()> {
+        self.hasher.update(data).map_err(to_io_error)?;
+        self.bytes += data.len() as u64;
+        Ok(())
+    }
+}
+
+impl<R: Read, H: StreamingHash> Read for HashingReader<R, H> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.check()?;
+        let n = self.inner.read(buf)?;
+        self.hash(&buf[..n])?;
+        Ok(n)
+    }
+}
+
+impl<R: BufRead, H: StreamingHash> BufRead for HashingReader<R, H> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.fill().map(|(buf, _)| buf)
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let len = amt.min(self.filled);
+        if len > 0 && self.error.is_none() {
+            // The consumed bytes are still at the front of the inner buffer,
+            // which is known to be non-empty, so fill_buf returns it without I/O
+            let result = match self.inner.fill_buf() {
+                Ok(buf) => self.hasher.update(&buf[..len]),
+                Err(err) => Err(err.into()),
+            };
+            match result {
+                Ok(()) => self.bytes += len as u64,
+                Err(err) => self.error = Some(err),
+            }
+            self.filled -= len;
+        }
+        self.inner.consume(amt);
+    }
+}
+
+/// Writer adapter that hashes every byte written through it
+///
+/// Only the bytes the inner writer accepts are hashed, so short writes and
+/// retried `write_all` calls never hash anything twice.
+#[derive(Debug)]
+pub struct HashingWriter<W, H> {
+    inner: W,
+    hasher: H,
+    bytes: u64,
+}
+
+impl<W, H: StreamingHash> HashingWriter<W, H> {
+    /// Wrap `inner`, feeding everything written into `hasher`
+    pub fn new(inner: W, hasher: H) -> Self {
+        Self { inner, hasher, bytes: 0 }
+    }
+
+    /// Number of bytes written so far
+    pub fn byte_count(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Canonical digest of the bytes written so far
+    pub fn digest_bytes(&self) -> Vec<u8> {
+        self.hasher.digest_bytes()
+    }
+
+    /// Streaming state, for typed digests
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Mutable access to the wrapped writer; bytes written through it
+    /// directly are not hashed
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> (W, H) {
+        (self.inner, self.hasher)
+    }
+}
+
+impl<W: Write, H: StreamingHash> Write for HashingWriter<W, H> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]).map_err(to_io_error)?;
+        self.bytes += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reader that checks the data against an expected digest at EOF
+///
+/// Reads pass through unchanged until the inner reader is exhausted. If the
+/// data does not hash to `expected` (canonical big-endian bytes), the read
+/// that would report EOF fails with `io::ErrorKind::InvalidData` wrapping
+/// `XXHashError::ChecksumMismatch`, and keeps failing on later reads.
+///
+/// `new` fails with `XXHashError::InvalidInputLength` when `expected` is not
+/// `hasher.output_size()` bytes long, since no data could ever match it.
+#[derive(Debug)]
+pub struct VerifyingReader<R, H> {
+    reader: HashingReader<R, H>,
+    expected: Vec<u8>,
+}
+
+impl<R, H: StreamingHash> VerifyingReader<R, H> {
+    /// Wrap `inner`, expecting its contents to hash to `expected` under `hasher`
+    pub fn new(inner: R, hasher: H, expected: &[u8]) -> XXHashResult<Self> {
+        if expected.len() != hasher.output_size() {
+            return Err(XXHashError::InvalidInputLength(expected.len()));
+        }
+        Ok(Self { reader: HashingReader::new(inner, hasher), expected: expected.to_vec() })
+    }
+
+    /// Number of bytes read so far
+    pub fn byte_count(&self) -> u64 {
+        self.reader.byte_count()
+    }
+
+    pub fn get_ref(&self) -> &R {
+        self.reader.get_ref()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader.into_inner().0
+    }
+}
+
+fn verify<H: StreamingHash>(hasher: &H, expected: &[u8]) -> io::Result<()> {
+    let actual = hasher.digest_bytes();
+    if actual == expected {
+        return Ok(());
+    }
+    let err = XXHashError::ChecksumMismatch { expected: expected.to_vec(), actual };
+    Err(io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+impl<R: Read, H: StreamingHash> Read for VerifyingReader<R, H> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        if n == 0 && !buf.is_empty() {
+            verify(self.reader.hasher(), &self.expected)?;
+        }
+        Ok(n)
+    }
+}
+
+impl<R: BufRead, H: StreamingHash> BufRead for VerifyingReader<R, H> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        let (buf, hasher) = self.reader.fill()?;
+        if buf.is_empty() {
+            verify(hasher, &self.expected)?;
+        }
+        Ok(buf)
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.reader.consume(amt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xxh3::{xxh3_128bits, xxh3_64bits_with_seed};
+    use crate::xxh32::xxh32;
+    use crate::xxh64::xxh64;
+
+    fn sample(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 31 % 251) as u8).collect()
+    }
+
+    #[test]
+    fn test_io_copy_into_states() {
+        let data = sample(200_000);
+
+        let mut state = XXH64State::new(0);
+        io::copy(&mut &data[..], &mut state).unwrap();
+        assert_eq!(state.digest(), xxh64(&data));
+
+        let mut state = XXH3State::new_with_seed(17);
+        io::copy(&mut &data[..], &mut state).unwrap();
+        assert_eq!(state.digest_64(), xxh3_64bits_with_seed(&data, 17));
+
+        let mut state = Algorithm::XXH32.hasher();
+        io::copy(&mut &data[..], &mut state).unwrap();
+        assert_eq!(state.digest_bytes(), xxh32(&data).to_be_bytes());
+    }
+
+    #[test]
+    fn test_hash_reader() {
+        // Longer than one read buffer, and not a multiple of it
+        let data = sample(READ_BUFFER_SIZE * 2 + 123);
+        assert_eq!(hash_reader(Algorithm::XXH64, &data[..]).unwrap(), xxh64(&data).to_be_bytes());
+        assert_eq!(hash_reader(Algorithm::XXH128, &data[..]).unwrap(), xxh3_128bits(&data).to_be_bytes());
+
+        let mut state = XXH128Stream::default();
+        assert_eq!(hash_reader_into(&mut &data[..], &mut state).unwrap(), data.len() as u64);
+    }
+
+    #[test]
+    fn test_hash_file() {
+        let data = sample(100_000);
+        let path = std::env::temp_dir().join(format!("xxhash_io_test_{}", std::process::id()));
+        std::fs::write(&path, &data).unwrap();
+        let digest = hash_file(Algorithm::XXH3_64, &path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(digest.unwrap(), crate::xxh3::xxh3_64bits(&data).to_be_bytes());
+
+        let missing = std::env::temp_dir().join("xxhash_io_test_missing");
+        assert_eq!(
+            hash_file(Algorithm::XXH64, &missing),
+            Err(XXHashError::Io { kind: io::ErrorKind::NotFound, path: Some(missing.clone()) })
+        );
+    }
+
+    #[test]
+    fn test_read_error_is_reported() {
+        struct FailingReader;
+        impl Read for FailingReader {
+            fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::from(io::ErrorKind::BrokenPipe))
+            }
+        }
+        assert_eq!(
+            hash_reader(Algorithm::XXH32, FailingReader),
+            Err(XXHashError::Io { kind: io::ErrorKind::BrokenPipe, path: None })
+        );
+    }
+
+    /// Reader that returns at most `step` bytes per call
+    struct ShortReader<'a> {
+        data: &'a [u8],
+        step: usize,
+    }
+
+    impl Read for ShortReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = buf.len().min(self.step).min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_hashing_reader_short_reads() {
+        let data = sample(10_000);
+        let mut reader = HashingReader::new(ShortReader { data: &data, step: 7 }, XXH64State::new(3));
+        let mut head = [0u8; 100];
+        reader.read_exact(&mut head).unwrap();
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+
+        assert_eq!([&head[..], &rest[..]].concat(), data);
+        assert_eq!(reader.byte_count(), data.len() as u64);
+        assert_eq!(reader.hasher().digest(), crate::xxh64::xxh64_with_seed(&data, 3));
+    }
+
+    #[test]
+    fn test_hashing_reader_bufread() {
+        let text = "first line\nsecond line\nno newline at end";
+        let reader = io::BufReader::with_capacity(8, text.as_bytes());
+        let mut reader = HashingReader::new(reader, XXH3_64Stream::default());
+        let lines: Vec<String> = (&mut reader).lines().map(Result::unwrap).collect();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(reader.byte_count(), text.len() as u64);
+        assert_eq!(reader.hasher().0.digest_64(), crate::xxh3::xxh3_64bits(text.as_bytes()));
+
+        // fill_buf alone does not count as reading
+        let mut reader = HashingReader::new(text.as_bytes(), XXH32State::new(0));
+        assert_eq!(reader.fill_buf().unwrap().len(), text.len());
+        assert_eq!(reader.byte_count(), 0);
+        reader.consume(5);
+        assert_eq!(reader.digest_bytes().unwrap(), xxh32(b"first").to_be_bytes());
+    }
+
+    #[test]
+    fn test_hashing_reader_keeps_consume_errors() {
+        /// Buffered reader whose second fill_buf fails
+        struct FlakyReader {
+            data: &'static [u8],
+            fills: usize,
+        }
+        impl Read for FlakyReader {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.data.read(buf)
+            }
+        }
+        impl BufRead for FlakyReader {
+            fn fill_buf(&mut self) -> io::Result<&[u8]> {
+                self.fills += 1;
+                if self.fills == 2 {
+                    return Err(io::Error::from(io::ErrorKind::ConnectionReset));
+                }
+                Ok(self.data)
+            }
+            fn consume(&mut self, amt: usize) {
+                self.data = &self.data[amt..];
+            }
+        }
+
+        let mut reader = HashingReader::new(FlakyReader { data: b"abcdef", fills: 0 }, XXH64State::new(0));
+        assert_eq!(reader.fill_buf().unwrap(), b"abcdef");
+        reader.consume(3);
+
+        let lost = XXHashError::Io { kind: io::ErrorKind::ConnectionReset, path: None };
+        assert_eq!(reader.digest_bytes(), Err(lost));
+        assert_eq!(reader.fill_buf().unwrap_err().kind(), io::ErrorKind::ConnectionReset);
+        assert_eq!(reader.read(&mut [0u8; 4]).unwrap_err().kind(), io::ErrorKind::ConnectionReset);
+        assert_eq!(reader.byte_count(), 0);
+    }
+
+    /// Reader that counts calls to `read`
+    struct CountingReader<'a> {
+        data: &'a [u8],
+        reads: usize,
+    }
+
+    impl Read for CountingReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.reads += 1;
+            self.data.read(buf)
+        }
+    }
+
+    #[test]
+    fn test_hashing_reader_bufread_does_no_extra_reads() {
+        let inner = io::BufReader::new(CountingReader { data: b"a\nb\n", reads: 0 });
+        let mut reader = HashingReader::new(inner, XXH64State::new(0));
+        let lines = (&mut reader).lines().count();
+
+        assert_eq!(lines, 2);
+        // One read for the data and one that sees EOF, as with a plain BufReader
+        assert_eq!(reader.get_ref().get_ref().reads, 2);
+        assert_eq!(reader.hasher().digest(), xxh64(b"a\nb\n"));
+    }
+
+    #[test]
+    fn test_hashing_writer_short_writes() {
+        /// Writer that accepts at most 5 bytes per call
+        struct ShortWriter(Vec<u8>);
+        impl Write for ShortWriter {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                let n = buf.len().min(5);
+                self.0.extend_from_slice(&buf[..n]);
+                Ok(n)
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let data = sample(1_000);
+        let mut writer = HashingWriter::new(ShortWriter(Vec::new()), Algorithm::XXH128.hasher());
+        writer.write_all(&data).unwrap();
+        writer.flush().unwrap();
+
+        assert_eq!(writer.byte_count(), data.len() as u64);
+        assert_eq!(writer.digest_bytes(), xxh3_128bits(&data).to_be_bytes());
+        let (inner, _) = writer.into_inner();
+        assert_eq!(inner.0, data);
+    }
+
+    fn mismatch(err: &io::Error) -> Option<&XXHashError> {
+        err.get_ref().and_then(|inner| inner.downcast_ref::<XXHashError>())
+    }
+
+    #[test]
+    fn test_verifying_reader_accepts_matching_data() {
+        let data = sample(150_000);
+        let seed = 0x9e3779b185ebca8d;
+        let hasher = XXH3_64Stream(XXH3State::new_with_seed(seed));
+        let expected = xxh3_64bits_with_seed(&data, seed).to_be_bytes();
+        let mut reader = VerifyingReader::new(&data[..], hasher, &expected).unwrap();
+
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).unwrap();
+        assert_eq!(out, data);
+        assert_eq!(reader.byte_count(), data.len() as u64);
+
+        for algorithm in Algorithm::ALL {
+            let expected = hash_reader(algorithm, &data[..]).unwrap();
+            let mut reader = VerifyingReader::new(io::BufReader::new(&data[..]), algorithm.hasher(), &expected).unwrap();
+            assert_eq!(io::copy(&mut reader, &mut io::sink()).unwrap(), data.len() as u64, "{}", algorithm);
+        }
+    }
+
+    #[test]
+    fn test_verifying_reader_fails_on_corruption() {
+        let mut data = sample(5_000);
+        let expected = xxh64(&data).to_be_bytes();
+        data[1234] ^= 1;
+        let actual = xxh64(&data).to_be_bytes();
+
+        let mut reader = VerifyingReader::new(&data[..], XXH64State::new(0), &expected).unwrap();
+        let err = reader.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            mismatch(&err),
+            Some(&XXHashError::ChecksumMismatch { expected: expected.to_vec(), actual: actual.to_vec() })
+        );
+        // EOF stays an error
+        assert!(reader.read(&mut [0u8; 16]).is_err());
+        // An empty read is not EOF
+        assert_eq!(reader.read(&mut []).unwrap(), 0);
+
+        let expected = xxh3_128bits(b"expected").to_be_bytes();
+        let mut reader = VerifyingReader::new(io::BufReader::new(&b"actual"[..]), XXH128Stream::default(), &expected).unwrap();
+        let err = reader.read_line(&mut String::new()).unwrap_err();
+        assert!(matches!(mismatch(&err), Some(XXHashError::ChecksumMismatch { .. })));
+    }
+
+    #[test]
+    fn test_verifying_reader_checks_expected_length() {
+        let expected = xxh64(b"data").to_be_bytes();
+        let err = VerifyingReader::new(&b"data"[..], XXH3_64Stream::default(), &expected[..4]).err();
+        assert_eq!(err, Some(XXHashError::InvalidInputLength(4)));
+        let err = VerifyingReader::new(&b"data"[..], XXH128Stream::default(), &expected).err();
+        assert_eq!(err, Some(XXHashError::InvalidInputLength(8)));
+    }
+
+    #[test]
+    fn test_verifying_reader_bufread_reads() {
+        let data = b"a\nb\n";
+        let inner = io::BufReader::new(CountingReader { data, reads: 0 });
+        let mut reader = VerifyingReader::new(inner, XXH64State::new(0), &xxh64(data).to_be_bytes()).unwrap();
+        assert_eq!((&mut reader).lines().count(), 2);
+        assert_eq!(reader.get_ref().get_ref().reads, 2);
+    }
+}
@@ -14,19 +14,58 @@
 //! 
 //! All functions use Result<T, E> for error handling and Option<T> for nullable values.
 //! No panics occur in normal control flow.
+//!
+//! ## Features
+//!
+//! - **std** (default): `std::io` adapters, runtime SIMD detection and
+//!   `RandomXXH3BuildHasher`. Implies `alloc`.
+//! - **alloc**: `to_checkpoint`, `Algorithm` and `StreamingHash`, the
+//!   error variants carrying text or bytes, and XXH3 secrets longer than
+//!   `XXH3_SECRET_INLINE_MAX` bytes.
+//!
+//! Without `std` the crate is `no_std`; the one-shot and streaming XXH32,
+//! XXH64 and XXH3 APIs, the `Hasher` types and `const fn` hashing are
+//! always available.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
 
 pub mod xxh32;
 pub mod xxh64; 
 pub mod xxh3;
+pub mod xxh3_backend;
 pub mod error;
 pub mod constants;
+pub mod hasher;
+#[cfg(feature = "alloc")]
+pub mod algorithm;
+#[cfg(feature = "std")]
+pub mod io;
+pub mod checkpoint;
+pub mod const_hash;
 
 pub use error::{XXHashError, XXHashResult};
-pub use xxh32::{XXH32State, xxh32, xxh32_with_seed};
-pub use xxh64::{XXH64State, xxh64, xxh64_with_seed};
+pub use xxh32::{XXH32State, XXH32Canonical, xxh32, xxh32_with_seed, xxh32_u32, xxh32_u32_with_seed};
+pub use xxh64::{XXH64State, XXH64Canonical, xxh64, xxh64_with_seed, xxh64_u64, xxh64_u64_with_seed};
 pub use xxh3::{XXH3State, xxh3_64bits, xxh3_64bits_with_seed, xxh3_64bits_with_secret, 
+               xxh3_64bits_with_secret_and_seed, xxh3_64_u64, xxh3_64_u64_with_seed,
+               xxh3_64_u128, xxh3_64_u128_with_seed,
                xxh3_128bits, xxh3_128bits_with_seed, xxh3_128bits_with_secret,
-               XXH128Hash, generate_secret_from_seed};
+               xxh3_128bits_with_secret_and_seed,
+               XXH128Hash, XXH128Canonical, generate_secret, generate_secret_from_seed};
+pub use hasher::{XXH32Hasher, XXH64Hasher, XXH3Hasher, XXH32BuildHasher, XXH64BuildHasher,
+                 XXH3BuildHasher};
+#[cfg(feature = "std")]
+pub use hasher::RandomXXH3BuildHasher;
+#[cfg(feature = "alloc")]
+pub use algorithm::{Algorithm, StreamingHash, XXH3_64Stream, XXH128Stream};
+pub use const_hash::{xxh32_const, xxh64_const, xxh3_64bits_const};
+pub use xxh3_backend::{force_xxh3_backend, XXH3Backend};
+#[cfg(feature = "std")]
+pub use io::{hash_reader, hash_reader_into, hash_file, HashingReader, HashingWriter,
+             VerifyingReader};
 
 /// Version information
 pub const VERSION: &str = "0.8.1";
@@ -41,7 +80,7 @@ pub fn validate_input(_data: &[u8]) -> XXHashResult<()> {
 /// Check if secret is valid for XXH3
 #[inline] 
 pub fn validate_secret(secret: &[u8]) -> XXHashResult<()> {
-    if secret.len() < 136 {
+    if secret.len() < constants::XXH3_SECRET_SIZE_MIN {
         return Err(XXHashError::InvalidSecretSize(secret.len()));
     }
     Ok(())
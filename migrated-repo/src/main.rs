@@ -46,12 +46,12 @@ fn print_hash_3_64_seed(input: &str, seed: u64) {
 
 fn print_hash_3_128(input: &str) {
     let hash = xxh3_128bits(input.as_bytes());
-    println!("XXH3_128('{}') = 0x{:016x}{:016x}", input, hash.high, hash.low);
+    println!("XXH3_128('{}') = {:#x}", input, hash);
 }
 
 fn print_hash_3_128_seed(input: &str, seed: u64) {
     let hash = xxh3_128bits_with_seed(input.as_bytes(), seed);
-    println!("XXH3_128('{}', 0x{:016x}) = 0x{:016x}{:016x}", input, seed, hash.high, hash.low);
+    println!("XXH3_128('{}', 0x{:016x}) = {:#x}", input, seed, hash);
 }
 
 fn print_hash_3_64_secret(input: &str) {
@@ -67,7 +67,7 @@ fn print_hash_3_128_secret(input: &str) {
     // Create a custom secret based on the default secret
     let custom_secret = generate_secret_from_seed(0);
     match xxh3_128bits_with_secret(input.as_bytes(), &custom_secret) {
-        Ok(hash) => println!("XXH3_128_secret('{}') = 0x{:016x}{:016x}", input, hash.high, hash.low),
+        Ok(hash) => println!("XXH3_128_secret('{}') = {:#x}", input, hash),
         Err(e) => eprintln!("Error computing XXH3_128_secret: {}", e),
     }
 }
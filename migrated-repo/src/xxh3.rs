@@ -1,13 +1,28 @@
 //! XXH3 hash algorithm implementation - Modern 64-bit and 128-bit hash functions
 
+use core::cmp::Ordering;
+use core::fmt;
+#[cfg(feature = "alloc")]
+use core::str::FromStr;
+
+#[cfg(feature = "alloc")]
+use crate::checkpoint::CheckpointWriter;
+use crate::checkpoint::{CheckpointReader, TAG_XXH3};
 use crate::constants::*;
 use crate::error::{XXHashError, XXHashResult};
+#[cfg(feature = "alloc")]
+use alloc::{string::ToString, vec::Vec};
+use crate::validate_secret;
+use crate::xxh3_backend::XXH3Kernels;
 
 /// XXH3 64-bit hash type
 pub type XXH3_64Hash = u64;
 
 /// XXH3 128-bit hash type
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+///
+/// Formats as the 32-digit hex string printed by xxhsum (`{}`, `{:x}`, `{:X}`)
+/// and parses back from it with `str::parse`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct XXH128Hash {
     pub high: u64,
     pub low: u64,
@@ -17,24 +32,199 @@ impl XXH128Hash {
     pub fn new(high: u64, low: u64) -> Self {
         Self { high, low }
     }
+
+    /// Big-endian bytes, high half first
+    pub fn to_be_bytes(&self) -> [u8; 16] {
+        let mut bytes = [0u8; 16];
+        bytes[..8].copy_from_slice(&self.high.to_be_bytes());
+        bytes[8..].copy_from_slice(&self.low.to_be_bytes());
+        bytes
+    }
+
+    /// Build a hash from big-endian bytes, high half first
+    pub fn from_be_bytes(bytes: [u8; 16]) -> Self {
+        Self::new(read_u64_be(&bytes[..8]), read_u64_be(&bytes[8..]))
+    }
+
+    /// Convert to the canonical form
+    pub fn to_canonical(&self) -> XXH128Canonical {
+        XXH128Canonical { digest: self.to_be_bytes() }
+    }
+
+    /// Convert from the canonical form
+    pub fn from_canonical(canonical: &XXH128Canonical) -> Self {
+        Self::from_be_bytes(canonical.digest)
+    }
+}
+
+/// Orders hashes like C XXH128_cmp: high half first, then low half
+impl Ord for XXH128Hash {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.high.cmp(&other.high).then(self.low.cmp(&other.low))
+    }
+}
+
+impl PartialOrd for XXH128Hash {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<u128> for XXH128Hash {
+    fn from(value: u128) -> Self {
+        Self::new((value >> 64) as u64, value as u64)
+    }
+}
+
+impl From<XXH128Hash> for u128 {
+    fn from(hash: XXH128Hash) -> Self {
+        ((hash.high as u128) << 64) | hash.low as u128
+    }
+}
+
+impl fmt::LowerHex for XXH128Hash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+        write!(f, "{:016x}{:016x}", self.high, self.low)
+    }
+}
+
+impl fmt::UpperHex for XXH128Hash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+        write!(f, "{:016X}{:016X}", self.high, self.low)
+    }
+}
+
+impl fmt::Display for XXH128Hash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(self, f)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl FromStr for XXH128Hash {
+    type Err = XXHashError;
+
+    /// Parse exactly 32 hex digits, with an optional `0x` prefix
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")).unwrap_or(s);
+        if digits.len() != 32 || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(XXHashError::InvalidHexDigest(s.to_string()));
+        }
+        u128::from_str_radix(digits, 16)
+            .map(Self::from)
+            .map_err(|_| XXHashError::InvalidHexDigest(s.to_string()))
+    }
+}
+
+/// Canonical (big-endian) representation of an XXH128 hash - matches C XXH128_canonical_t
+///
+/// Canonical forms are what should be written to disk or sent over the
+/// wire: the bytes are the same on every platform and read in the same
+/// order as xxhsum's hex output.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct XXH128Canonical {
+    pub digest: [u8; 16],
+}
+
+impl XXH128Canonical {
+    /// Convert a hash to its canonical form
+    pub fn from_hash(hash: XXH128Hash) -> Self {
+        hash.to_canonical()
+    }
+
+    /// Convert the canonical form back to a hash
+    pub fn to_hash(&self) -> XXH128Hash {
+        XXH128Hash::from_canonical(self)
+    }
+
+    /// Read a canonical hash from the start of a byte slice
+    pub fn from_slice(bytes: &[u8]) -> XXHashResult<Self> {
+        if bytes.len() < 16 {
+            return Err(XXHashError::BufferTooSmall { required: 16, available: bytes.len() });
+        }
+        let mut digest = [0u8; 16];
+        digest.copy_from_slice(&bytes[..16]);
+        Ok(Self { digest })
+    }
+}
+
+impl From<XXH128Hash> for XXH128Canonical {
+    fn from(hash: XXH128Hash) -> Self {
+        hash.to_canonical()
+    }
+}
+
+impl From<XXH128Canonical> for XXH128Hash {
+    fn from(canonical: XXH128Canonical) -> Self {
+        canonical.to_hash()
+    }
+}
+
+/// Secret owned by an `XXH3State`
+///
+/// Secrets up to `XXH3_SECRET_INLINE_MAX` bytes, including every secret
+/// derived from a seed, are kept inline so no allocation is needed; larger
+/// ones go to the heap and need the `alloc` feature.
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)] // inline on purpose, so seeded states never allocate
+enum CustomSecret {
+    Inline { bytes: [u8; XXH3_SECRET_INLINE_MAX], len: usize },
+    #[cfg(feature = "alloc")]
+    Heap(Vec<u8>),
+}
+
+impl CustomSecret {
+    /// Copy a secret that already passed `validate_secret`
+    fn new(secret: &[u8]) -> XXHashResult<Self> {
+        if secret.len() <= XXH3_SECRET_INLINE_MAX {
+            let mut bytes = [0u8; XXH3_SECRET_INLINE_MAX];
+            bytes[..secret.len()].copy_from_slice(secret);
+            return Ok(CustomSecret::Inline { bytes, len: secret.len() });
+        }
+        #[cfg(feature = "alloc")]
+        return Ok(CustomSecret::Heap(secret.to_vec()));
+        #[cfg(not(feature = "alloc"))]
+        Err(XXHashError::InvalidSecretSize(secret.len()))
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        match self {
+            CustomSecret::Inline { bytes, len } => &bytes[..*len],
+            #[cfg(feature = "alloc")]
+            CustomSecret::Heap(bytes) => bytes,
+        }
+    }
 }
 
-/// XXH3 streaming state (simplified)
+/// XXH3 streaming state
+///
+/// Custom secrets are copied into the state. Without the `alloc` feature
+/// they are limited to `XXH3_SECRET_INLINE_MAX` bytes.
 #[derive(Debug, Clone)]
 pub struct XXH3State {
-    #[allow(dead_code)]
-    acc: [u64; 8],
-    custom_secret: Option<Vec<u8>>,
+    acc: [u64; XXH3_ACC_NB],
+    /// Caller-supplied secret, or the secret derived from a non-zero seed
+    custom_secret: Option<CustomSecret>,
     seed: u64,
+    /// Whether short inputs are hashed with the seed and the default secret
+    use_seed: bool,
     total_len: u64,
-    buffer: [u8; 256],
+    buffer: [u8; XXH3_INTERNALBUFFER_SIZE],
     buffered_size: usize,
-    #[allow(dead_code)]
-    stripe_len: usize,
-    #[allow(dead_code)]
+    nb_stripes_so_far: usize,
     nb_stripes_per_block: usize,
-    #[allow(dead_code)]
-    nb_blocks: usize,
+}
+
+impl Default for XXH3State {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl XXH3State {
@@ -45,36 +235,52 @@ impl XXH3State {
 
     /// Create new state with seed
     pub fn new_with_seed(seed: u64) -> Self {
-        Self {
-            acc: Self::init_acc(seed),
-            custom_secret: None,
-            seed,
-            total_len: 0,
-            buffer: [0; 256],
-            buffered_size: 0,
-            stripe_len: 64,
-            nb_stripes_per_block: (XXH3_SECRET_DEFAULT_SIZE - 64) / 8,
-            nb_blocks: 0,
-        }
+        let custom_secret = if seed == 0 {
+            None
+        } else {
+            // Always fits inline
+            Some(CustomSecret::new(&generate_secret_from_seed(seed)).unwrap())
+        };
+        Self::new_internal(custom_secret, seed, seed != 0)
+    }
+
+    /// Seeded state from a secret already derived with `generate_secret_from_seed`
+    pub(crate) fn with_derived_secret(seed: u64, secret: &[u8; XXH3_SECRET_DEFAULT_SIZE]) -> Self {
+        // Always fits inline
+        Self::new_internal(Some(CustomSecret::new(secret).unwrap()), seed, true)
     }
 
     /// Create new state with custom secret
+    ///
+    /// Any secret of at least `XXH3_SECRET_SIZE_MIN` bytes is accepted; its
+    /// length determines the block size used for long inputs.
     pub fn new_with_secret(secret: &[u8]) -> XXHashResult<Self> {
-        if secret.len() < XXH3_SECRET_SIZE_MIN {
-            return Err(XXHashError::InvalidSecretSize(secret.len()));
-        }
+        validate_secret(secret)?;
+        Ok(Self::new_internal(Some(CustomSecret::new(secret)?), 0, false))
+    }
+
+    /// Create new state with both a custom secret and a seed
+    ///
+    /// Inputs up to 240 bytes are hashed with the seed and the default secret,
+    /// longer inputs with the custom secret, as in `xxh3_64bits_with_secret_and_seed`.
+    pub fn new_with_secret_and_seed(secret: &[u8], seed: u64) -> XXHashResult<Self> {
+        validate_secret(secret)?;
+        Ok(Self::new_internal(Some(CustomSecret::new(secret)?), seed, true))
+    }
 
-        Ok(Self {
-            acc: Self::init_acc(0),
-            custom_secret: Some(secret.to_vec()),
-            seed: 0,
+    fn new_internal(custom_secret: Option<CustomSecret>, seed: u64, use_seed: bool) -> Self {
+        let secret_size = custom_secret.as_ref().map_or(XXH3_SECRET_DEFAULT_SIZE, |secret| secret.as_slice().len());
+        Self {
+            acc: XXH3_INIT_ACC,
+            custom_secret,
+            seed,
+            use_seed,
             total_len: 0,
-            buffer: [0; 256],
+            buffer: [0; XXH3_INTERNALBUFFER_SIZE],
             buffered_size: 0,
-            stripe_len: 64,
-            nb_stripes_per_block: (secret.len() - 64) / 8,
-            nb_blocks: 0,
-        })
+            nb_stripes_so_far: 0,
+            nb_stripes_per_block: (secret_size - XXH3_STRIPE_LEN) / XXH3_SECRET_CONSUME_RATE,
+        }
     }
 
     /// Reset state
@@ -95,41 +301,238 @@ impl XXH3State {
         Ok(())
     }
 
-    /// Update with new data (simplified implementation)
+    /// Reset state with secret and seed
+    pub fn reset_with_secret_and_seed(&mut self, secret: &[u8], seed: u64) -> XXHashResult<()> {
+        *self = Self::new_with_secret_and_seed(secret, seed)?;
+        Ok(())
+    }
+
+    /// Start a new hash, keeping the current seed and secret
+    #[cfg(feature = "alloc")]
+    pub(crate) fn restart(&mut self) {
+        self.acc = XXH3_INIT_ACC;
+        self.total_len = 0;
+        self.buffered_size = 0;
+        self.nb_stripes_so_far = 0;
+    }
+
+    /// Update with new data
     pub fn update(&mut self, data: &[u8]) -> XXHashResult<()> {
         self.total_len = self.total_len.wrapping_add(data.len() as u64);
-        // For simplicity, just buffer the data and use one-shot in digest
-        if self.buffered_size + data.len() <= self.buffer.len() {
+
+        // Small input: just buffer it
+        if data.len() <= XXH3_INTERNALBUFFER_SIZE - self.buffered_size {
             self.buffer[self.buffered_size..self.buffered_size + data.len()].copy_from_slice(data);
             self.buffered_size += data.len();
+            return Ok(());
+        }
+
+        let secret = match &self.custom_secret {
+            Some(secret) => secret.as_slice(),
+            None => &XXH3_DEFAULT_SECRET,
+        };
+        let kernels = XXH3Kernels::active();
+        let mut input = data;
+
+        // Complete and consume the internal buffer first
+        if self.buffered_size > 0 {
+            let load_size = XXH3_INTERNALBUFFER_SIZE - self.buffered_size;
+            self.buffer[self.buffered_size..].copy_from_slice(&input[..load_size]);
+            input = &input[load_size..];
+            xxh3_consume_stripes(
+                kernels,
+                &mut self.acc,
+                &mut self.nb_stripes_so_far,
+                self.nb_stripes_per_block,
+                &self.buffer,
+                XXH3_INTERNALBUFFER_SIZE / XXH3_STRIPE_LEN,
+                secret,
+            );
+            self.buffered_size = 0;
+        }
+
+        // Consume input directly, always keeping at least one byte for the buffer
+        if input.len() > XXH3_INTERNALBUFFER_SIZE {
+            let nb_stripes = (input.len() - 1) / XXH3_STRIPE_LEN;
+            xxh3_consume_stripes(
+                kernels,
+                &mut self.acc,
+                &mut self.nb_stripes_so_far,
+                self.nb_stripes_per_block,
+                input,
+                nb_stripes,
+                secret,
+            );
+            let consumed = nb_stripes * XXH3_STRIPE_LEN;
+
+            // Keep the last consumed stripe around, digest may need it
+            self.buffer[XXH3_INTERNALBUFFER_SIZE - XXH3_STRIPE_LEN..]
+                .copy_from_slice(&input[consumed - XXH3_STRIPE_LEN..consumed]);
+            input = &input[consumed..];
         }
+
+        self.buffer[..input.len()].copy_from_slice(input);
+        self.buffered_size = input.len();
         Ok(())
     }
 
     /// Get 64-bit digest
     pub fn digest_64(&self) -> XXH3_64Hash {
         let secret = self.get_secret();
-        xxh3_64bits_internal(&self.buffer[..self.buffered_size], secret, self.seed)
+        if self.total_len > XXH3_MIDSIZE_MAX as u64 {
+            let acc = self.digest_long(secret);
+            return xxh3_merge_accs(
+                &acc,
+                &secret[XXH3_SECRET_MERGEACCS_START..],
+                self.total_len.wrapping_mul(XXH64_PRIME1),
+            );
+        }
+
+        // Short input: everything is still in the buffer
+        let input = &self.buffer[..self.total_len as usize];
+        if self.use_seed {
+            xxh3_64bits_with_seed(input, self.seed)
+        } else {
+            xxh3_64bits_internal(input, secret, 0)
+        }
     }
 
-    /// Get 128-bit digest  
+    /// Get 128-bit digest
     pub fn digest_128(&self) -> XXH128Hash {
         let secret = self.get_secret();
-        xxh3_128bits_internal(&self.buffer[..self.buffered_size], secret, self.seed)
+        if self.total_len > XXH3_MIDSIZE_MAX as u64 {
+            let acc = self.digest_long(secret);
+            return XXH128Hash::new(
+                xxh3_merge_accs(
+                    &acc,
+                    &secret[secret.len() - XXH3_ACC_NB * 8 - XXH3_SECRET_MERGEACCS_START..],
+                    !self.total_len.wrapping_mul(XXH64_PRIME2),
+                ),
+                xxh3_merge_accs(
+                    &acc,
+                    &secret[XXH3_SECRET_MERGEACCS_START..],
+                    self.total_len.wrapping_mul(XXH64_PRIME1),
+                ),
+            );
+        }
+
+        let input = &self.buffer[..self.total_len as usize];
+        if self.use_seed {
+            xxh3_128bits_with_seed(input, self.seed)
+        } else {
+            xxh3_128bits_internal(input, secret, 0)
+        }
     }
 
-    fn init_acc(seed: u64) -> [u64; 8] {
-        let secret = &XXH3_DEFAULT_SECRET[64..];
-        let mut acc = [0u64; 8];
-        for i in 0..8 {
-            let secret_val = read_u64_le(&secret[i * 8..]);
-            acc[i] = seed ^ secret_val;
+    /// Serialize the full state, including the seed and secret, into a
+    /// versioned checkpoint
+    ///
+    /// `from_checkpoint` restores a state that continues exactly where this
+    /// one stopped, in any process.
+    #[cfg(feature = "alloc")]
+    pub fn to_checkpoint(&self) -> Vec<u8> {
+        let mut out = CheckpointWriter::new(TAG_XXH3);
+        out.put_u64(self.seed);
+        out.put_bool(self.use_seed);
+        match &self.custom_secret {
+            Some(secret) => {
+                let secret = secret.as_slice();
+                out.put_bool(true);
+                out.put_u32(secret.len() as u32);
+                out.put_bytes(secret);
+            }
+            None => out.put_bool(false),
         }
+        out.put_u64(self.total_len);
+        for lane in self.acc {
+            out.put_u64(lane);
+        }
+        out.put_u64(self.nb_stripes_so_far as u64);
+        out.put_u32(self.buffered_size as u32);
+        // The whole buffer: the last consumed stripe may still be needed by digest
+        out.put_bytes(&self.buffer);
+        out.finish()
+    }
+
+    /// Restore a state from `to_checkpoint` output
+    pub fn from_checkpoint(checkpoint: &[u8]) -> XXHashResult<Self> {
+        let mut input = CheckpointReader::new(checkpoint, TAG_XXH3)?;
+        let seed = input.get_u64()?;
+        let use_seed = input.get_bool()?;
+        let custom_secret = if input.get_bool()? {
+            let len = input.get_u32()? as usize;
+            let secret = input.get_bytes(len)?;
+            validate_secret(secret).map_err(|_| XXHashError::InvalidState)?;
+            Some(CustomSecret::new(secret).map_err(|_| XXHashError::InvalidState)?)
+        } else {
+            None
+        };
+        // A non-zero seed on long input needs the secret derived from it
+        if use_seed && seed != 0 && custom_secret.is_none() {
+            return Err(XXHashError::InvalidState);
+        }
+        let mut state = Self::new_internal(custom_secret, seed, use_seed);
+
+        state.total_len = input.get_u64()?;
+        for lane in &mut state.acc {
+            *lane = input.get_u64()?;
+        }
+        let nb_stripes_so_far = input.get_u64()?;
+        let buffered_size = input.get_u32()? as usize;
+        state.buffer = input.get_array()?;
+        input.finish()?;
+
+        let short_input = state.total_len <= XXH3_INTERNALBUFFER_SIZE as u64;
+        if nb_stripes_so_far >= state.nb_stripes_per_block as u64
+            || buffered_size > XXH3_INTERNALBUFFER_SIZE
+            || (short_input && buffered_size as u64 != state.total_len)
+            || (!short_input && buffered_size == 0)
+        {
+            return Err(XXHashError::InvalidState);
+        }
+        state.nb_stripes_so_far = nb_stripes_so_far as usize;
+        state.buffered_size = buffered_size;
+        Ok(state)
+    }
+
+    /// Finish accumulation on a copy of the accumulators, leaving the state untouched
+    fn digest_long(&self, secret: &[u8]) -> [u64; XXH3_ACC_NB] {
+        let mut acc = self.acc;
+        let secret_limit = secret.len() - XXH3_STRIPE_LEN;
+        let kernels = XXH3Kernels::active();
+        let mut last_stripe = [0u8; XXH3_STRIPE_LEN];
+
+        let last_stripe_ref: &[u8] = if self.buffered_size >= XXH3_STRIPE_LEN {
+            let nb_stripes = (self.buffered_size - 1) / XXH3_STRIPE_LEN;
+            let mut nb_stripes_so_far = self.nb_stripes_so_far;
+            xxh3_consume_stripes(
+                kernels,
+                &mut acc,
+                &mut nb_stripes_so_far,
+                self.nb_stripes_per_block,
+                &self.buffer,
+                nb_stripes,
+                secret,
+            );
+            &self.buffer[self.buffered_size - XXH3_STRIPE_LEN..self.buffered_size]
+        } else {
+            // Rebuild the last stripe from the end of the previous buffer contents
+            let catchup_size = XXH3_STRIPE_LEN - self.buffered_size;
+            last_stripe[..catchup_size]
+                .copy_from_slice(&self.buffer[XXH3_INTERNALBUFFER_SIZE - catchup_size..]);
+            last_stripe[catchup_size..].copy_from_slice(&self.buffer[..self.buffered_size]);
+            &last_stripe
+        };
+
+        kernels.accumulate(&mut acc, last_stripe_ref, &secret[secret_limit - XXH3_SECRET_LASTACC_START..], 1);
         acc
     }
 
     fn get_secret(&self) -> &[u8] {
-        self.custom_secret.as_deref().unwrap_or(&XXH3_DEFAULT_SECRET)
+        match &self.custom_secret {
+            Some(secret) => secret.as_slice(),
+            None => &XXH3_DEFAULT_SECRET,
+        }
     }
 }
 
@@ -146,14 +549,55 @@ pub fn xxh3_64bits_with_seed(data: &[u8], seed: u64) -> XXH3_64Hash {
     xxh3_64bits_internal(data, &XXH3_DEFAULT_SECRET, seed)
 }
 
+/// Compute XXH3 64-bit hash of a `u64` key, equal to `xxh3_64bits(&key.to_le_bytes())`
+#[inline]
+pub fn xxh3_64_u64(key: u64) -> XXH3_64Hash {
+    xxh3_64_u64_with_seed(key, 0)
+}
+
+/// Compute XXH3 64-bit hash of a `u64` key with seed
+///
+/// Goes straight to the 4-8 byte kernel instead of dispatching on length.
+#[inline]
+pub fn xxh3_64_u64_with_seed(key: u64, seed: u64) -> XXH3_64Hash {
+    xxh3_len_4to8_64b(&key.to_le_bytes(), &XXH3_DEFAULT_SECRET, seed)
+}
+
+/// Compute XXH3 64-bit hash of a `u128` key, equal to `xxh3_64bits(&key.to_le_bytes())`
+#[inline]
+pub fn xxh3_64_u128(key: u128) -> XXH3_64Hash {
+    xxh3_64_u128_with_seed(key, 0)
+}
+
+/// Compute XXH3 64-bit hash of a `u128` key with seed
+///
+/// Goes straight to the 9-16 byte kernel instead of dispatching on length.
+#[inline]
+pub fn xxh3_64_u128_with_seed(key: u128, seed: u64) -> XXH3_64Hash {
+    xxh3_len_9to16_64b(&key.to_le_bytes(), &XXH3_DEFAULT_SECRET, seed)
+}
+
 /// Compute XXH3 64-bit hash with custom secret
+///
+/// The secret may be any size of at least `XXH3_SECRET_SIZE_MIN` bytes.
 pub fn xxh3_64bits_with_secret(data: &[u8], secret: &[u8]) -> XXHashResult<XXH3_64Hash> {
-    if secret.len() < XXH3_SECRET_SIZE_MIN {
-        return Err(XXHashError::InvalidSecretSize(secret.len()));
-    }
+    validate_secret(secret)?;
     Ok(xxh3_64bits_internal(data, secret, 0))
 }
 
+/// Compute XXH3 64-bit hash with both a custom secret and a seed
+///
+/// Inputs up to 240 bytes are hashed with the seed and the default secret,
+/// longer inputs with the custom secret only. This avoids deriving a new
+/// secret for every seed while keeping seeded results for short inputs.
+pub fn xxh3_64bits_with_secret_and_seed(data: &[u8], secret: &[u8], seed: u64) -> XXHashResult<XXH3_64Hash> {
+    validate_secret(secret)?;
+    if data.len() <= XXH3_MIDSIZE_MAX {
+        return Ok(xxh3_64bits_with_seed(data, seed));
+    }
+    Ok(xxh3_hashlong_64b(data, secret))
+}
+
 /// Compute XXH3 128-bit hash
 pub fn xxh3_128bits(data: &[u8]) -> XXH128Hash {
     xxh3_128bits_internal(data, &XXH3_DEFAULT_SECRET, 0)
@@ -168,26 +612,79 @@ pub fn xxh3_128bits_with_seed(data: &[u8], seed: u64) -> XXH128Hash {
 }
 
 /// Compute XXH3 128-bit hash with custom secret
+///
+/// The secret may be any size of at least `XXH3_SECRET_SIZE_MIN` bytes.
 pub fn xxh3_128bits_with_secret(data: &[u8], secret: &[u8]) -> XXHashResult<XXH128Hash> {
-    if secret.len() < XXH3_SECRET_SIZE_MIN {
-        return Err(XXHashError::InvalidSecretSize(secret.len()));
-    }
+    validate_secret(secret)?;
     Ok(xxh3_128bits_internal(data, secret, 0))
 }
 
-/// Generate secret from seed (simplified - not identical to C version)
-pub fn generate_secret_from_seed(seed: u64) -> Vec<u8> {
-    let mut secret = XXH3_DEFAULT_SECRET.to_vec();
-    
-    // Simple seed-based modification of the default secret
-    for (i, byte) in secret.iter_mut().enumerate() {
-        let derived = seed.wrapping_mul(PRIME_MX1).wrapping_add(i as u64);
-        *byte ^= (derived & 0xFF) as u8;
+/// Compute XXH3 128-bit hash with both a custom secret and a seed
+///
+/// Same split as `xxh3_64bits_with_secret_and_seed`.
+pub fn xxh3_128bits_with_secret_and_seed(data: &[u8], secret: &[u8], seed: u64) -> XXHashResult<XXH128Hash> {
+    validate_secret(secret)?;
+    if data.len() <= XXH3_MIDSIZE_MAX {
+        return Ok(xxh3_128bits_with_seed(data, seed));
+    }
+    Ok(xxh3_hashlong_128b(data, secret))
+}
+
+/// Generate secret from seed - matches C XXH3_initCustomSecret / XXH3_generateSecret_fromSeed
+///
+/// Each 16-byte pair of words of the default secret gets the seed added to
+/// its low word and subtracted from its high word.
+///
+/// This is a `const fn` so the `const_hash` module can derive seeded secrets
+/// at compile time.
+pub const fn generate_secret_from_seed(seed: u64) -> [u8; XXH3_SECRET_DEFAULT_SIZE] {
+    let mut secret = XXH3_DEFAULT_SECRET;
+    let mut i = 0;
+    while i < XXH3_SECRET_DEFAULT_SIZE / 16 {
+        let (lo, rest) = secret.split_at_mut(16 * i).1.split_at_mut(8);
+        let hi = rest.split_at_mut(8).0;
+        let lo_val = read_u64_le(lo).wrapping_add(seed);
+        let hi_val = read_u64_le(hi).wrapping_sub(seed);
+        lo.copy_from_slice(&lo_val.to_le_bytes());
+        hi.copy_from_slice(&hi_val.to_le_bytes());
+        i += 1;
     }
-    
     secret
 }
 
+/// Generate a secret of any size from arbitrary seed material - matches C XXH3_generateSecret
+///
+/// `secret` must be at least `XXH3_SECRET_SIZE_MIN` bytes long. An empty
+/// `custom_seed` falls back to the default secret as seed material.
+pub fn generate_secret(secret: &mut [u8], custom_seed: &[u8]) -> XXHashResult<()> {
+    if secret.len() < XXH3_SECRET_SIZE_MIN {
+        return Err(XXHashError::InvalidSecretSize(secret.len()));
+    }
+    let custom_seed = if custom_seed.is_empty() {
+        &XXH3_DEFAULT_SECRET[..]
+    } else {
+        custom_seed
+    };
+
+    // Fill the secret with copies of the seed material
+    for chunk in secret.chunks_mut(custom_seed.len()) {
+        chunk.copy_from_slice(&custom_seed[..chunk.len()]);
+    }
+
+    // Scramble every 16-byte segment with a hash of the canonical seed digest
+    let seed_hash = xxh3_128bits(custom_seed);
+    let scrambler = seed_hash.to_be_bytes();
+
+    let secret_size = secret.len();
+    for n in 0..secret_size / 16 {
+        let h128 = xxh3_128bits_with_seed(&scrambler, n as u64);
+        xxh3_combine16(&mut secret[16 * n..], h128);
+    }
+    // Last segment, which may overlap the previous one
+    xxh3_combine16(&mut secret[secret_size - 16..], seed_hash);
+    Ok(())
+}
+
 // Internal implementation functions matching C source structure
 
 /// Main XXH3_64 internal function
@@ -200,8 +697,10 @@ fn xxh3_64bits_internal(data: &[u8], secret: &[u8], seed: u64) -> u64 {
         xxh3_len_17to128_64b(data, secret, seed)
     } else if len <= XXH3_MIDSIZE_MAX {
         xxh3_len_129to240_64b(data, secret, seed)
+    } else if seed == 0 {
+        xxh3_hashlong_64b(data, secret)
     } else {
-        xxh3_hashlong_64b(data, secret, seed)
+        xxh3_hashlong_64b(data, &generate_secret_from_seed(seed))
     }
 }
 
@@ -215,13 +714,18 @@ fn xxh3_128bits_internal(data: &[u8], secret: &[u8], seed: u64) -> XXH128Hash {
         xxh3_len_17to128_128b(data, secret, seed)
     } else if len <= XXH3_MIDSIZE_MAX {
         xxh3_len_129to240_128b(data, secret, seed)
+    } else if seed == 0 {
+        xxh3_hashlong_128b(data, secret)
     } else {
-        xxh3_hashlong_128b(data, secret, seed)
+        xxh3_hashlong_128b(data, &generate_secret_from_seed(seed))
     }
 }
 
 /// XXH3 length 0-16 bytes (64-bit) - matches C implementation exactly
-fn xxh3_len_0to16_64b(data: &[u8], secret: &[u8], seed: u64) -> u64 {
+///
+/// This and the helpers below are `const fn` for the `const_hash` module,
+/// which is why they read at offsets with `split_at` rather than slicing.
+pub(crate) const fn xxh3_len_0to16_64b(data: &[u8], secret: &[u8], seed: u64) -> u64 {
     let len = data.len();
     
     if len > 8 {
@@ -231,265 +735,423 @@ fn xxh3_len_0to16_64b(data: &[u8], secret: &[u8], seed: u64) -> u64 {
     } else if len > 0 {
         xxh3_len_1to3_64b(data, secret, seed)
     } else {
-        // Empty input case - matches C: XXH3_avalanche(seed ^ (XXH_readLE64(secret+56) ^ XXH_readLE64(secret+64)))
-        xxh3_avalanche(seed ^ (read_u64_le(&secret[56..]) ^ read_u64_le(&secret[64..])))
+        // Empty input case - matches C: XXH64_avalanche(seed ^ (XXH_readLE64(secret+56) ^ XXH_readLE64(secret+64)))
+        xxh64_avalanche(seed ^ (read_u64_le(secret.split_at(56).1) ^ read_u64_le(secret.split_at(64).1)))
     }
 }
 
 /// XXH3 length 1-3 bytes - matches C implementation
-fn xxh3_len_1to3_64b(data: &[u8], secret: &[u8], seed: u64) -> u64 {
+const fn xxh3_len_1to3_64b(data: &[u8], secret: &[u8], seed: u64) -> u64 {
     let len = data.len();
     let c1 = data[0] as u32;
     let c2 = data[len >> 1] as u32;
     let c3 = data[len - 1] as u32;
-    let combined = (c1 << 16) | (c2 << 24) | (c3 << 0) | ((len as u32) << 8);
-    let bitflip = ((read_u32_le(&secret[0..]) ^ read_u32_le(&secret[4..])) as u64).wrapping_add(seed);
+    let combined = (c1 << 16) | (c2 << 24) | c3 | ((len as u32) << 8);
+    let bitflip = ((read_u32_le(secret) ^ read_u32_le(secret.split_at(4).1)) as u64).wrapping_add(seed);
     let keyed = (combined as u64) ^ bitflip;
-    xxh3_avalanche(keyed)
+    xxh64_avalanche(keyed)
 }
 
 /// XXH3 length 4-8 bytes - matches C implementation  
-fn xxh3_len_4to8_64b(data: &[u8], secret: &[u8], seed: u64) -> u64 {
+#[inline]
+const fn xxh3_len_4to8_64b(data: &[u8], secret: &[u8], seed: u64) -> u64 {
     let len = data.len();
     let seed = seed ^ ((seed as u32).swap_bytes() as u64) << 32;
-    let input1 = read_u32_le(&data[0..]) as u64;
-    let input2 = read_u32_le(&data[len - 4..]) as u64;
-    let bitflip = (read_u64_le(&secret[8..]) ^ read_u64_le(&secret[16..])).wrapping_sub(seed);
+    let input1 = read_u32_le(data) as u64;
+    let input2 = read_u32_le(data.split_at(len - 4).1) as u64;
+    let bitflip = (read_u64_le(secret.split_at(8).1) ^ read_u64_le(secret.split_at(16).1)).wrapping_sub(seed);
     let input64 = input2.wrapping_add(input1 << 32);
     let keyed = input64 ^ bitflip;
     xxh3_rrmxmx(keyed, len as u64)
 }
 
 /// XXH3 length 9-16 bytes - matches C implementation
-fn xxh3_len_9to16_64b(data: &[u8], secret: &[u8], seed: u64) -> u64 {
+#[inline]
+const fn xxh3_len_9to16_64b(data: &[u8], secret: &[u8], seed: u64) -> u64 {
     let len = data.len();
-    let bitflipl = (read_u64_le(&secret[24..]) ^ read_u64_le(&secret[32..])).wrapping_add(seed);
-    let bitfliph = (read_u64_le(&secret[40..]) ^ read_u64_le(&secret[48..])).wrapping_sub(seed);
-    let input_lo = read_u64_le(&data[0..]);
-    let input_hi = read_u64_le(&data[len - 8..]);
+    let bitflipl = (read_u64_le(secret.split_at(24).1) ^ read_u64_le(secret.split_at(32).1)).wrapping_add(seed);
+    let bitfliph = (read_u64_le(secret.split_at(40).1) ^ read_u64_le(secret.split_at(48).1)).wrapping_sub(seed);
+    let input_lo = read_u64_le(data) ^ bitflipl;
+    let input_hi = read_u64_le(data.split_at(len - 8).1) ^ bitfliph;
     let acc = (len as u64)
         .wrapping_add(input_lo.swap_bytes())
         .wrapping_add(input_hi)
-        .wrapping_add(xxh3_mul128_fold64(input_lo ^ bitflipl, input_hi ^ bitfliph));
+        .wrapping_add(xxh3_mul128_fold64(input_lo, input_hi));
     xxh3_avalanche(acc)
 }
 
-/// XXH3 length 17-128 bytes - more accurate implementation
+/// XXH3 length 17-128 bytes - matches C implementation
 fn xxh3_len_17to128_64b(data: &[u8], secret: &[u8], seed: u64) -> u64 {
     let len = data.len();
-    let mut acc = (len as u64).wrapping_mul(PRIME_MX1);
-    
-    // Process data in 16-byte chunks
-    let mut i = 0;
-    while i + 16 <= len {
-        let chunk = &data[i..i+16];
-        let secret_chunk = &secret[i % 16..];
-        
-        acc = acc.wrapping_add(xxh3_mix16b(chunk, secret_chunk, seed));
-        i += 16;
-    }
-    
-    // Handle remaining data
-    if i < len {
-        let remaining = &data[i..];
-        if remaining.len() >= 16 {
-            acc = acc.wrapping_add(xxh3_mix16b(remaining, &secret[0..], seed));
+    let mut acc = (len as u64).wrapping_mul(XXH64_PRIME1);
+
+    // Mix pairs of 16-byte blocks taken from both ends of the input
+    if len > 32 {
+        if len > 64 {
+            if len > 96 {
+                acc = acc.wrapping_add(xxh3_mix16b(&data[48..], &secret[96..], seed));
+                acc = acc.wrapping_add(xxh3_mix16b(&data[len - 64..], &secret[112..], seed));
+            }
+            acc = acc.wrapping_add(xxh3_mix16b(&data[32..], &secret[64..], seed));
+            acc = acc.wrapping_add(xxh3_mix16b(&data[len - 48..], &secret[80..], seed));
         }
+        acc = acc.wrapping_add(xxh3_mix16b(&data[16..], &secret[32..], seed));
+        acc = acc.wrapping_add(xxh3_mix16b(&data[len - 32..], &secret[48..], seed));
     }
-    
+    acc = acc.wrapping_add(xxh3_mix16b(&data[0..], &secret[0..], seed));
+    acc = acc.wrapping_add(xxh3_mix16b(&data[len - 16..], &secret[16..], seed));
+
     xxh3_avalanche(acc)
 }
 
-/// XXH3 length 129-240 bytes - simplified implementation
+/// XXH3 length 129-240 bytes - matches C implementation
 fn xxh3_len_129to240_64b(data: &[u8], secret: &[u8], seed: u64) -> u64 {
     let len = data.len();
-    let mut acc = (len as u64).wrapping_mul(PRIME_MX1);
     let nb_rounds = len / 16;
-    
-    // First 8 rounds
-    for i in 0..8.min(nb_rounds) {
+    let mut acc = (len as u64).wrapping_mul(XXH64_PRIME1);
+
+    // First 8 rounds use the start of the secret
+    for i in 0..8 {
         acc = acc.wrapping_add(xxh3_mix16b(&data[16 * i..], &secret[16 * i..], seed));
     }
+
+    // Last 16 bytes always use the same offset, valid for any secret >= XXH3_SECRET_SIZE_MIN
+    let mut acc_end = xxh3_mix16b(
+        &data[len - 16..],
+        &secret[XXH3_SECRET_SIZE_MIN - XXH3_MIDSIZE_LASTOFFSET..],
+        seed,
+    );
     acc = xxh3_avalanche(acc);
-    
-    // Remaining rounds
+
+    // Remaining rounds restart near the beginning of the secret
     for i in 8..nb_rounds {
-        let secret_offset = 16 * (i - 8) + XXH3_MIDSIZE_STARTOFFSET;
-        if secret_offset + 16 <= secret.len() {
-            acc = acc.wrapping_add(xxh3_mix16b(&data[16 * i..], &secret[secret_offset..], seed));
-        }
-    }
-    
-    // Last 16 bytes
-    if len >= 16 {
-        let secret_offset = XXH3_SECRET_DEFAULT_SIZE - XXH3_MIDSIZE_LASTOFFSET - 16;
-        acc = acc.wrapping_add(xxh3_mix16b(&data[len - 16..], &secret[secret_offset..], seed));
+        acc_end = acc_end.wrapping_add(xxh3_mix16b(
+            &data[16 * i..],
+            &secret[16 * (i - 8) + XXH3_MIDSIZE_STARTOFFSET..],
+            seed,
+        ));
     }
-    
-    xxh3_avalanche(acc)
+
+    xxh3_avalanche(acc.wrapping_add(acc_end))
 }
 
-/// XXH3 long hash - simplified implementation
-fn xxh3_hashlong_64b(data: &[u8], secret: &[u8], seed: u64) -> u64 {
-    // Simplified long hash implementation
-    let mut acc = seed.wrapping_mul(PRIME_MX1);
-    
-    for chunk in data.chunks(16) {
-        if chunk.len() == 16 {
-            acc = acc.wrapping_add(xxh3_mix16b(chunk, &secret[0..], seed));
-        }
-    }
-    
-    acc = acc.wrapping_add(data.len() as u64);
-    xxh3_avalanche(acc)
+/// XXH3 long hash (> 240 bytes) - matches C implementation
+fn xxh3_hashlong_64b(data: &[u8], secret: &[u8]) -> u64 {
+    let mut acc = XXH3_INIT_ACC;
+    xxh3_hashlong_internal_loop(&mut acc, data, secret);
+    xxh3_merge_accs(
+        &acc,
+        &secret[XXH3_SECRET_MERGEACCS_START..],
+        (data.len() as u64).wrapping_mul(XXH64_PRIME1),
+    )
 }
 
-/// XXH3 128-bit functions (simplified implementations based on 64-bit)
+/// XXH3 length 0-16 bytes (128-bit) - matches C implementation
 fn xxh3_len_0to16_128b(data: &[u8], secret: &[u8], seed: u64) -> XXH128Hash {
     let len = data.len();
-    
-    if len == 0 {
-        // Empty case - matches C implementation
-        XXH128Hash::new(
-            read_u64_le(&secret[64..]) ^ seed,
-            read_u64_le(&secret[72..]) ^ seed,
-        )
-    } else if len <= 8 {
-        let hash64 = xxh3_len_0to16_64b(data, secret, seed);
-        XXH128Hash::new(
-            hash64 ^ (read_u64_le(&secret[64..]).wrapping_add(seed)),
-            hash64 ^ (read_u64_le(&secret[72..]).wrapping_sub(seed)),
-        )
+
+    if len > 8 {
+        xxh3_len_9to16_128b(data, secret, seed)
+    } else if len >= 4 {
+        xxh3_len_4to8_128b(data, secret, seed)
+    } else if len > 0 {
+        xxh3_len_1to3_128b(data, secret, seed)
     } else {
-        let input_lo = read_u64_le(&data[0..]);
-        let input_hi = read_u64_le(&data[len - 8..]);
-        let bitflipl = (read_u64_le(&secret[24..]) ^ read_u64_le(&secret[32..])).wrapping_add(seed);
-        let bitfliph = (read_u64_le(&secret[40..]) ^ read_u64_le(&secret[48..])).wrapping_sub(seed);
-        let keyed_lo = input_lo ^ bitflipl;
-        let keyed_hi = input_hi ^ bitfliph;
+        // Empty case - matches C implementation
+        let bitflipl = read_u64_le(&secret[64..]) ^ read_u64_le(&secret[72..]);
+        let bitfliph = read_u64_le(&secret[80..]) ^ read_u64_le(&secret[88..]);
         XXH128Hash::new(
-            xxh3_avalanche(keyed_lo),
-            xxh3_avalanche(keyed_hi),
+            xxh64_avalanche(seed ^ bitfliph),
+            xxh64_avalanche(seed ^ bitflipl),
         )
     }
 }
 
+/// XXH3 length 1-3 bytes (128-bit) - matches C implementation
+fn xxh3_len_1to3_128b(data: &[u8], secret: &[u8], seed: u64) -> XXH128Hash {
+    let len = data.len();
+    let c1 = data[0] as u32;
+    let c2 = data[len >> 1] as u32;
+    let c3 = data[len - 1] as u32;
+    let combinedl = (c1 << 16) | (c2 << 24) | c3 | ((len as u32) << 8);
+    let combinedh = rotl32(combinedl.swap_bytes(), 13);
+    let bitflipl = ((read_u32_le(&secret[0..]) ^ read_u32_le(&secret[4..])) as u64).wrapping_add(seed);
+    let bitfliph = ((read_u32_le(&secret[8..]) ^ read_u32_le(&secret[12..])) as u64).wrapping_sub(seed);
+    let keyed_lo = (combinedl as u64) ^ bitflipl;
+    let keyed_hi = (combinedh as u64) ^ bitfliph;
+    XXH128Hash::new(xxh64_avalanche(keyed_hi), xxh64_avalanche(keyed_lo))
+}
+
+/// XXH3 length 4-8 bytes (128-bit) - matches C implementation
+fn xxh3_len_4to8_128b(data: &[u8], secret: &[u8], seed: u64) -> XXH128Hash {
+    let len = data.len();
+    let seed = seed ^ ((seed as u32).swap_bytes() as u64) << 32;
+    let input_lo = read_u32_le(&data[0..]) as u64;
+    let input_hi = read_u32_le(&data[len - 4..]) as u64;
+    let input64 = input_lo.wrapping_add(input_hi << 32);
+    let bitflip = (read_u64_le(&secret[16..]) ^ read_u64_le(&secret[24..])).wrapping_add(seed);
+    let keyed = input64 ^ bitflip;
+
+    // Shift len to the left to ensure it is even, this avoids even multiplies
+    let mut m128 = xxh_mult64to128(keyed, XXH64_PRIME1.wrapping_add((len as u64) << 2));
+    m128.high = m128.high.wrapping_add(m128.low << 1);
+    m128.low ^= m128.high >> 3;
+    m128.low = xxh_xorshift64(m128.low, 35);
+    m128.low = m128.low.wrapping_mul(PRIME_MX2);
+    m128.low = xxh_xorshift64(m128.low, 28);
+    m128.high = xxh3_avalanche(m128.high);
+    m128
+}
+
+/// XXH3 length 9-16 bytes (128-bit) - matches C implementation
+fn xxh3_len_9to16_128b(data: &[u8], secret: &[u8], seed: u64) -> XXH128Hash {
+    let len = data.len();
+    let bitflipl = (read_u64_le(&secret[32..]) ^ read_u64_le(&secret[40..])).wrapping_sub(seed);
+    let bitfliph = (read_u64_le(&secret[48..]) ^ read_u64_le(&secret[56..])).wrapping_add(seed);
+    let input_lo = read_u64_le(&data[0..]);
+    let mut input_hi = read_u64_le(&data[len - 8..]);
+    let mut m128 = xxh_mult64to128(input_lo ^ input_hi ^ bitflipl, XXH64_PRIME1);
+
+    // Put len in the middle of m128 to ensure that the length gets mixed to both the low and high bits
+    m128.low = m128.low.wrapping_add(((len - 1) as u64) << 54);
+    input_hi ^= bitfliph;
+    m128.high = m128
+        .high
+        .wrapping_add(input_hi)
+        .wrapping_add((input_hi & 0xFFFF_FFFF).wrapping_mul(XXH32_PRIME2 as u64 - 1));
+    m128.low ^= m128.high.swap_bytes();
+
+    let mut h128 = xxh_mult64to128(m128.low, XXH64_PRIME2);
+    h128.high = h128.high.wrapping_add(m128.high.wrapping_mul(XXH64_PRIME2));
+    XXH128Hash::new(xxh3_avalanche(h128.high), xxh3_avalanche(h128.low))
+}
+
+/// XXH3 length 17-128 bytes (128-bit) - matches C implementation
 fn xxh3_len_17to128_128b(data: &[u8], secret: &[u8], seed: u64) -> XXH128Hash {
     let len = data.len();
-    let mut acc = [seed, seed, seed, seed, seed, seed, seed, seed];
-    
-    // Process data in 16-byte chunks
-    let mut i = 0;
-    while i + 16 <= len {
-        let chunk = &data[i..i+16];
-        let secret_chunk = &secret[i % 16..];
-        
-        acc[0] = acc[0].wrapping_add(xxh3_mix16b(chunk, secret_chunk, seed));
-        i += 16;
-    }
-    
-    // Handle remaining data
-    if i < len {
-        let remaining = &data[i..];
-        if remaining.len() >= 16 {
-            acc[0] = acc[0].wrapping_add(xxh3_mix16b(remaining, &secret[0..], seed));
+    let mut acc = XXH128Hash::new(0, (len as u64).wrapping_mul(XXH64_PRIME1));
+
+    if len > 32 {
+        if len > 64 {
+            if len > 96 {
+                acc = xxh128_mix32b(acc, &data[48..], &data[len - 64..], &secret[96..], seed);
+            }
+            acc = xxh128_mix32b(acc, &data[32..], &data[len - 48..], &secret[64..], seed);
         }
+        acc = xxh128_mix32b(acc, &data[16..], &data[len - 32..], &secret[32..], seed);
     }
-    
-    // Final mixing for 128-bit
-    let h128 = xxh3_avalanche(acc[0] ^ acc[1] ^ acc[2] ^ acc[3] ^ acc[4] ^ acc[5] ^ acc[6] ^ acc[7]);
-    let l128 = xxh3_avalanche(acc[0].wrapping_add(acc[1]).wrapping_add(acc[2]).wrapping_add(acc[3])
-                              .wrapping_add(acc[4]).wrapping_add(acc[5]).wrapping_add(acc[6]).wrapping_add(acc[7]));
-    
-    XXH128Hash::new(h128, l128)
+    acc = xxh128_mix32b(acc, data, &data[len - 16..], secret, seed);
+
+    xxh128_mid_finalize(acc, len, seed)
 }
 
+/// XXH3 length 129-240 bytes (128-bit) - matches C implementation
 fn xxh3_len_129to240_128b(data: &[u8], secret: &[u8], seed: u64) -> XXH128Hash {
     let len = data.len();
-    let mut acc = [seed, seed, seed, seed, seed, seed, seed, seed];
-    
-    // Process data in 16-byte chunks
-    let mut i = 0;
-    while i + 16 <= len {
-        let chunk = &data[i..i+16];
-        let secret_chunk = &secret[i % 16..];
-        
-        acc[0] = acc[0].wrapping_add(xxh3_mix16b(chunk, secret_chunk, seed));
-        i += 16;
+    let nb_rounds = len / 32;
+    let mut acc = XXH128Hash::new(0, (len as u64).wrapping_mul(XXH64_PRIME1));
+
+    for i in 0..4 {
+        acc = xxh128_mix32b(acc, &data[32 * i..], &data[32 * i + 16..], &secret[32 * i..], seed);
     }
-    
-    // Handle remaining data
-    if i < len {
-        let remaining = &data[i..];
-        if remaining.len() >= 16 {
-            acc[0] = acc[0].wrapping_add(xxh3_mix16b(remaining, &secret[0..], seed));
-        }
+    acc.low = xxh3_avalanche(acc.low);
+    acc.high = xxh3_avalanche(acc.high);
+
+    for i in 4..nb_rounds {
+        acc = xxh128_mix32b(
+            acc,
+            &data[32 * i..],
+            &data[32 * i + 16..],
+            &secret[XXH3_MIDSIZE_STARTOFFSET + 32 * (i - 4)..],
+            seed,
+        );
     }
-    
-    // Final mixing
-    let h128 = xxh3_avalanche(acc[0] ^ acc[1] ^ acc[2] ^ acc[3] ^ acc[4] ^ acc[5] ^ acc[6] ^ acc[7]);
-    let l128 = xxh3_avalanche(acc[0].wrapping_add(acc[1]).wrapping_add(acc[2]).wrapping_add(acc[3])
-                              .wrapping_add(acc[4]).wrapping_add(acc[5]).wrapping_add(acc[6]).wrapping_add(acc[7]));
-    
-    XXH128Hash::new(h128, l128)
+
+    // Last bytes
+    acc = xxh128_mix32b(
+        acc,
+        &data[len - 16..],
+        &data[len - 32..],
+        &secret[XXH3_SECRET_SIZE_MIN - XXH3_MIDSIZE_LASTOFFSET - 16..],
+        0u64.wrapping_sub(seed),
+    );
+
+    xxh128_mid_finalize(acc, len, seed)
 }
 
-fn xxh3_hashlong_128b(data: &[u8], secret: &[u8], seed: u64) -> XXH128Hash {
-    let len = data.len();
-    let mut acc = [seed, seed, seed, seed, seed, seed, seed, seed];
-    
-    // Process data in 16-byte chunks
-    let mut i = 0;
-    while i + 16 <= len {
-        let chunk = &data[i..i+16];
-        let secret_chunk = &secret[i % 16..];
-        
-        acc[0] = acc[0].wrapping_add(xxh3_mix16b(chunk, secret_chunk, seed));
-        i += 16;
-    }
-    
-    // Handle remaining data
-    if i < len {
-        let remaining = &data[i..];
-        if remaining.len() >= 16 {
-            acc[0] = acc[0].wrapping_add(xxh3_mix16b(remaining, &secret[0..], seed));
+/// XXH3 long hash (> 240 bytes, 128-bit) - matches C implementation
+fn xxh3_hashlong_128b(data: &[u8], secret: &[u8]) -> XXH128Hash {
+    let len = data.len() as u64;
+    let mut acc = XXH3_INIT_ACC;
+    xxh3_hashlong_internal_loop(&mut acc, data, secret);
+    XXH128Hash::new(
+        xxh3_merge_accs(
+            &acc,
+            &secret[secret.len() - XXH3_ACC_NB * 8 - XXH3_SECRET_MERGEACCS_START..],
+            !len.wrapping_mul(XXH64_PRIME2),
+        ),
+        xxh3_merge_accs(
+            &acc,
+            &secret[XXH3_SECRET_MERGEACCS_START..],
+            len.wrapping_mul(XXH64_PRIME1),
+        ),
+    )
+}
+
+// Helper functions
+
+/// XOR a 128-bit hash into 16 bytes of a secret
+fn xxh3_combine16(dst: &mut [u8], h128: XXH128Hash) {
+    let lo = read_u64_le(&dst[0..]) ^ h128.low;
+    let hi = read_u64_le(&dst[8..]) ^ h128.high;
+    dst[..8].copy_from_slice(&lo.to_le_bytes());
+    dst[8..16].copy_from_slice(&hi.to_le_bytes());
+}
+
+/// Consume stripes from a streaming input, scrambling whenever a block is completed
+fn xxh3_consume_stripes(
+    kernels: XXH3Kernels,
+    acc: &mut [u64; XXH3_ACC_NB],
+    nb_stripes_so_far: &mut usize,
+    nb_stripes_per_block: usize,
+    input: &[u8],
+    mut nb_stripes: usize,
+    secret: &[u8],
+) {
+    let secret_limit = secret.len() - XXH3_STRIPE_LEN;
+    let mut input = input;
+    let mut initial_secret = &secret[*nb_stripes_so_far * XXH3_SECRET_CONSUME_RATE..];
+
+    if nb_stripes >= nb_stripes_per_block - *nb_stripes_so_far {
+        // Finish the current block, then process whole blocks
+        let mut nb_stripes_this_iter = nb_stripes_per_block - *nb_stripes_so_far;
+        loop {
+            kernels.accumulate(acc, input, initial_secret, nb_stripes_this_iter);
+            kernels.scramble_acc(acc, &secret[secret_limit..]);
+            input = &input[nb_stripes_this_iter * XXH3_STRIPE_LEN..];
+            nb_stripes -= nb_stripes_this_iter;
+            nb_stripes_this_iter = nb_stripes_per_block;
+            initial_secret = secret;
+            if nb_stripes < nb_stripes_per_block {
+                break;
+            }
         }
+        *nb_stripes_so_far = 0;
+    }
+
+    // Partial block
+    if nb_stripes > 0 {
+        kernels.accumulate(acc, input, initial_secret, nb_stripes);
+        *nb_stripes_so_far += nb_stripes;
     }
-    
-    // Final mixing
-    let h128 = xxh3_avalanche(acc[0] ^ acc[1] ^ acc[2] ^ acc[3] ^ acc[4] ^ acc[5] ^ acc[6] ^ acc[7]);
-    let l128 = xxh3_avalanche(acc[0].wrapping_add(acc[1]).wrapping_add(acc[2]).wrapping_add(acc[3])
-                              .wrapping_add(acc[4]).wrapping_add(acc[5]).wrapping_add(acc[6]).wrapping_add(acc[7]));
-    
-    XXH128Hash::new(h128, l128)
 }
 
-// Helper functions
+/// Process all full blocks, the trailing stripes and the last stripe of a long input
+///
+/// The block length and the last-stripe offset both follow from the secret length.
+fn xxh3_hashlong_internal_loop(acc: &mut [u64; XXH3_ACC_NB], input: &[u8], secret: &[u8]) {
+    let len = input.len();
+    let secret_size = secret.len();
+    let nb_stripes_per_block = (secret_size - XXH3_STRIPE_LEN) / XXH3_SECRET_CONSUME_RATE;
+    let block_len = XXH3_STRIPE_LEN * nb_stripes_per_block;
+    let nb_blocks = (len - 1) / block_len;
+    let kernels = XXH3Kernels::active();
+
+    for n in 0..nb_blocks {
+        kernels.accumulate(acc, &input[n * block_len..], secret, nb_stripes_per_block);
+        kernels.scramble_acc(acc, &secret[secret_size - XXH3_STRIPE_LEN..]);
+    }
+
+    // Last partial block
+    let nb_stripes = ((len - 1) - block_len * nb_blocks) / XXH3_STRIPE_LEN;
+    kernels.accumulate(acc, &input[nb_blocks * block_len..], secret, nb_stripes);
+
+    // Last stripe, which may overlap the previous one
+    kernels.accumulate(
+        acc,
+        &input[len - XXH3_STRIPE_LEN..],
+        &secret[secret_size - XXH3_STRIPE_LEN - XXH3_SECRET_LASTACC_START..],
+        1,
+    );
+}
+
+#[inline]
+const fn xxh3_mix2accs(lo: u64, hi: u64, secret: &[u8]) -> u64 {
+    xxh3_mul128_fold64(lo ^ read_u64_le(secret), hi ^ read_u64_le(secret.split_at(8).1))
+}
+
+/// Merge the eight accumulators into a single 64-bit value
+pub(crate) const fn xxh3_merge_accs(acc: &[u64; XXH3_ACC_NB], secret: &[u8], start: u64) -> u64 {
+    let mut result = start;
+    let mut i = 0;
+    while i < 4 {
+        result = result.wrapping_add(xxh3_mix2accs(acc[2 * i], acc[2 * i + 1], secret.split_at(16 * i).1));
+        i += 1;
+    }
+    xxh3_avalanche(result)
+}
 
 /// XXH3 mix 16 bytes - matches C implementation
-fn xxh3_mix16b(input: &[u8], secret: &[u8], seed: u64) -> u64 {
-    let input_lo = read_u64_le(&input[0..]);
-    let input_hi = read_u64_le(&input[8..]);
-    let secret_lo = read_u64_le(&secret[0..]);
-    let secret_hi = read_u64_le(&secret[8..]);
-    
+#[inline]
+pub(crate) const fn xxh3_mix16b(input: &[u8], secret: &[u8], seed: u64) -> u64 {
+    let input_lo = read_u64_le(input);
+    let input_hi = read_u64_le(input.split_at(8).1);
+    let secret_lo = read_u64_le(secret);
+    let secret_hi = read_u64_le(secret.split_at(8).1);
+
     xxh3_mul128_fold64(
         input_lo ^ (secret_lo.wrapping_add(seed)),
         input_hi ^ (secret_hi.wrapping_sub(seed)),
     )
 }
 
+/// Mix two 16-byte blocks into both halves of a 128-bit accumulator
+#[inline]
+fn xxh128_mix32b(mut acc: XXH128Hash, input_1: &[u8], input_2: &[u8], secret: &[u8], seed: u64) -> XXH128Hash {
+    acc.low = acc.low.wrapping_add(xxh3_mix16b(input_1, secret, seed));
+    acc.low ^= read_u64_le(&input_2[0..]).wrapping_add(read_u64_le(&input_2[8..]));
+    acc.high = acc.high.wrapping_add(xxh3_mix16b(input_2, &secret[16..], seed));
+    acc.high ^= read_u64_le(&input_1[0..]).wrapping_add(read_u64_le(&input_1[8..]));
+    acc
+}
+
+/// Final merge shared by the 17-128 and 129-240 byte 128-bit paths
+#[inline]
+fn xxh128_mid_finalize(acc: XXH128Hash, len: usize, seed: u64) -> XXH128Hash {
+    let low = acc.low.wrapping_add(acc.high);
+    let high = acc
+        .low
+        .wrapping_mul(XXH64_PRIME1)
+        .wrapping_add(acc.high.wrapping_mul(XXH64_PRIME4))
+        .wrapping_add((len as u64).wrapping_sub(seed).wrapping_mul(XXH64_PRIME2));
+    XXH128Hash::new(0u64.wrapping_sub(xxh3_avalanche(high)), xxh3_avalanche(low))
+}
+
+/// Full 64x64 -> 128-bit multiply
+#[inline]
+fn xxh_mult64to128(lhs: u64, rhs: u64) -> XXH128Hash {
+    let product = (lhs as u128) * (rhs as u128);
+    XXH128Hash::new((product >> 64) as u64, product as u64)
+}
+
+#[inline]
+fn xxh_xorshift64(v: u64, shift: u32) -> u64 {
+    v ^ (v >> shift)
+}
+
 /// XXH3 128-bit multiply and fold to 64-bit
-fn xxh3_mul128_fold64(lhs: u64, rhs: u64) -> u64 {
+#[inline]
+pub(crate) const fn xxh3_mul128_fold64(lhs: u64, rhs: u64) -> u64 {
     let product = (lhs as u128) * (rhs as u128);
     (product as u64) ^ ((product >> 64) as u64)
 }
 
 /// XXH3 rrmxmx function - matches C implementation
-fn xxh3_rrmxmx(mut h: u64, len: u64) -> u64 {
+#[inline]
+pub(crate) const fn xxh3_rrmxmx(mut h: u64, len: u64) -> u64 {
     h ^= rotl64(h, 49) ^ rotl64(h, 24);
     h = h.wrapping_mul(PRIME_MX2);
-    h ^= (h >> 35) + len;
+    h ^= (h >> 35).wrapping_add(len);
     h = h.wrapping_mul(PRIME_MX2);
     h ^= h >> 28;
     h
@@ -509,10 +1171,105 @@ mod tests {
         assert_eq!(xxh3_64bits_with_seed(b"", 0x123456789abcdef0), 0x8aa56c2c3d8317f6);
     }
 
+    #[test]
+    fn test_generate_secret_from_seed() {
+        assert_eq!(generate_secret_from_seed(0), XXH3_DEFAULT_SECRET);
+
+        // Expected words from C XXH3_generateSecret_fromSeed
+        let secret = generate_secret_from_seed(0x123456789abcdef0);
+        assert_eq!(read_u64_le(&secret[0..]), 0xd07ffa9bd429dda8);
+        assert_eq!(read_u64_le(&secret[8..]), 0x0a78cb7e91c4228c);
+        assert_eq!(read_u64_le(&secret[184..]), 0x6c0bf543303ef8bf);
+    }
+
+    #[test]
+    fn test_xxh3_streaming_digest_is_non_destructive() {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 31 % 251) as u8).collect();
+        let mut state = XXH3State::new_with_seed(0x123456789abcdef0);
+        state.update(&data[..600]).unwrap();
+        assert_eq!(state.digest_64(), state.digest_64());
+        assert_eq!(state.digest_64(), xxh3_64bits_with_seed(&data[..600], 0x123456789abcdef0));
+
+        state.update(&data[600..]).unwrap();
+        assert_eq!(state.digest_64(), xxh3_64bits_with_seed(&data, 0x123456789abcdef0));
+        assert_eq!(state.digest_128(), xxh3_128bits_with_seed(&data, 0x123456789abcdef0));
+    }
+
+    #[test]
+    fn test_xxh128_canonical() {
+        let hash = xxh3_128bits(b"abc");
+        let canonical = hash.to_canonical();
+        assert_eq!(
+            canonical.digest,
+            [
+                0x06, 0xb0, 0x5a, 0xb6, 0x73, 0x3a, 0x61, 0x85,
+                0x78, 0xaf, 0x5f, 0x94, 0x89, 0x2f, 0x39, 0x50,
+            ]
+        );
+        assert_eq!(XXH128Hash::from_canonical(&canonical), hash);
+        assert_eq!(XXH128Hash::from_be_bytes(hash.to_be_bytes()), hash);
+        assert_eq!(XXH128Canonical::from_slice(&canonical.digest).unwrap().to_hash(), hash);
+        assert!(XXH128Canonical::from_slice(&canonical.digest[..15]).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_xxh128_value_traits() {
+        let hash = xxh3_128bits(b"abc");
+        let text = "06b05ab6733a618578af5f94892f3950";
+        assert_eq!(hash.to_string(), text);
+        assert_eq!(format!("{:x}", hash), text);
+        assert_eq!(format!("{:#x}", hash), format!("0x{}", text));
+        assert_eq!(format!("{:X}", hash), text.to_uppercase());
+        assert_eq!(text.parse::<XXH128Hash>(), Ok(hash));
+        assert_eq!(format!("0x{}", text).parse::<XXH128Hash>(), Ok(hash));
+        assert_eq!(u128::from(hash), 0x06b05ab6733a618578af5f94892f3950);
+        assert_eq!(XXH128Hash::from(0x06b05ab6733a618578af5f94892f3950u128), hash);
+
+        for bad in ["", "06b05ab6733a618578af5f94892f395", "06b05ab6733a618578af5f94892f3950f", "+6b05ab6733a618578af5f94892f3950", "g6b05ab6733a618578af5f94892f3950"] {
+            assert_eq!(bad.parse::<XXH128Hash>(), Err(XXHashError::InvalidHexDigest(bad.to_string())));
+        }
+    }
+
+    #[test]
+    fn test_xxh128_ordering() {
+        let a = XXH128Hash::new(1, u64::MAX);
+        let b = XXH128Hash::new(2, 0);
+        let c = XXH128Hash::new(2, 1);
+        assert!(a < b && b < c);
+        let mut hashes = vec![c, a, b];
+        hashes.sort();
+        assert_eq!(hashes, vec![a, b, c]);
+        assert_eq!(a.cmp(&a), Ordering::Equal);
+    }
+
     #[test]
     fn test_xxh3_128_empty() {
         let result = xxh3_128bits(b"");
         assert_eq!(result.high, 0x99aa06d3014798d8);
         assert_eq!(result.low, 0x6001c324468d497f);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_inline_secret_limit() {
+        let data: Vec<u8> = (0..3000u32).map(|i| (i * 131 % 251) as u8).collect();
+        let mut secret = [0u8; XXH3_SECRET_INLINE_MAX + 1];
+        generate_secret(&mut secret, b"inline").unwrap();
+
+        // The largest inline secret streams like the one-shot functions
+        let inline = &secret[..XXH3_SECRET_INLINE_MAX];
+        let mut state = XXH3State::new_with_secret(inline).unwrap();
+        state.update(&data).unwrap();
+        assert_eq!(state.digest_64(), xxh3_64bits_with_secret(&data, inline).unwrap());
+
+        // One byte more needs the heap
+        let result = XXH3State::new_with_secret(&secret);
+        if cfg!(feature = "alloc") {
+            let mut state = result.unwrap();
+            state.update(&data).unwrap();
+            assert_eq!(state.digest_128(), xxh3_128bits_with_secret(&data, &secret).unwrap());
+        } else {
+            assert_eq!(result.err(), Some(XXHashError::InvalidSecretSize(XXH3_SECRET_INLINE_MAX + 1)));
+        }
+    }
+}
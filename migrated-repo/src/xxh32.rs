@@ -1,18 +1,28 @@
 //! XXH32 hash algorithm implementation
 
+#[cfg(feature = "alloc")]
+use crate::checkpoint::CheckpointWriter;
+use crate::checkpoint::{CheckpointReader, TAG_XXH32};
 use crate::constants::*;
-use crate::error::XXHashResult;
+use crate::error::{XXHashError, XXHashResult};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
 
 /// XXH32 hash type
 pub type XXH32Hash = u32;
 
+/// Bytes consumed per round of the four lanes
+const XXH32_STRIPE_LEN: usize = 16;
+
+type Stripe = [u8; XXH32_STRIPE_LEN];
+
 /// XXH32 streaming state
 #[derive(Debug, Clone)]
 pub struct XXH32State {
     total_len: u64,
     large_len: bool,
     v: [u32; 4],
-    mem32: [u8; 16],
+    mem32: [u8; XXH32_STRIPE_LEN],
     memsize: usize,
     seed: u32,
 }
@@ -23,18 +33,18 @@ impl XXH32State {
         Self {
             total_len: 0,
             large_len: false,
-            v: [
-                seed.wrapping_add(XXH32_PRIME1).wrapping_add(XXH32_PRIME2),
-                seed.wrapping_add(XXH32_PRIME2),
-                seed,
-                seed.wrapping_sub(XXH32_PRIME1),
-            ],
-            mem32: [0; 16],
+            v: xxh32_init_lanes(seed),
+            mem32: [0; XXH32_STRIPE_LEN],
             memsize: 0,
             seed,
         }
     }
 
+    /// Seed this state was created with
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
     /// Reset state with new seed
     pub fn reset(&mut self, seed: u32) -> XXHashResult<()> {
         *self = Self::new(seed);
@@ -44,13 +54,13 @@ impl XXH32State {
     /// Update hash with new data
     pub fn update(&mut self, data: &[u8]) -> XXHashResult<()> {
         self.total_len = self.total_len.wrapping_add(data.len() as u64);
-        self.large_len |= (data.len() >= 16) | (self.total_len >= 16);
+        self.large_len |= (data.len() >= XXH32_STRIPE_LEN) | (self.total_len >= XXH32_STRIPE_LEN as u64);
 
         let mut input = data;
 
         // Fill buffer if we have leftover data
         if self.memsize > 0 {
-            let to_fill = 16 - self.memsize;
+            let to_fill = XXH32_STRIPE_LEN - self.memsize;
             if input.len() < to_fill {
                 // Not enough data to fill buffer
                 self.mem32[self.memsize..self.memsize + input.len()].copy_from_slice(input);
@@ -59,20 +69,14 @@ impl XXH32State {
             }
 
             // Fill buffer and process
-            self.mem32[self.memsize..16].copy_from_slice(&input[..to_fill]);
-            self.consume_buffer();
+            self.mem32[self.memsize..].copy_from_slice(&input[..to_fill]);
+            xxh32_stripe(&mut self.v, &self.mem32);
             input = &input[to_fill..];
             self.memsize = 0;
         }
 
-        // Process complete 16-byte chunks
-        while input.len() >= 16 {
-            self.v[0] = xxh32_round(self.v[0], read_u32_le(&input[0..]));
-            self.v[1] = xxh32_round(self.v[1], read_u32_le(&input[4..]));
-            self.v[2] = xxh32_round(self.v[2], read_u32_le(&input[8..]));
-            self.v[3] = xxh32_round(self.v[3], read_u32_le(&input[12..]));
-            input = &input[16..];
-        }
+        // Process complete stripes
+        let input = xxh32_consume_stripes(&mut self.v, input);
 
         // Store remaining data in buffer
         if !input.is_empty() {
@@ -85,55 +89,95 @@ impl XXH32State {
 
     /// Finalize and get hash
     pub fn digest(&self) -> XXH32Hash {
-        let mut h = if self.large_len {
-            rotl32(self.v[0], 1)
-                .wrapping_add(rotl32(self.v[1], 7))
-                .wrapping_add(rotl32(self.v[2], 12))
-                .wrapping_add(rotl32(self.v[3], 18))
+        let h = if self.large_len {
+            xxh32_merge_lanes(&self.v)
         } else {
             self.seed.wrapping_add(XXH32_PRIME5)
         };
+        xxh32_finalize(h, &self.mem32[..self.memsize], self.total_len)
+    }
 
-        h = h.wrapping_add(self.total_len as u32);
-
-        // Process remaining bytes
-        let mut remaining = &self.mem32[..self.memsize];
-        
-        // Process 4-byte chunks
-        while remaining.len() >= 4 {
-            h = h.wrapping_add(read_u32_le(remaining).wrapping_mul(XXH32_PRIME3));
-            h = rotl32(h, 17).wrapping_mul(XXH32_PRIME4);
-            remaining = &remaining[4..];
+    /// Serialize the full state into a versioned checkpoint
+    ///
+    /// `from_checkpoint` restores a state that continues exactly where this
+    /// one stopped, in any process.
+    #[cfg(feature = "alloc")]
+    pub fn to_checkpoint(&self) -> Vec<u8> {
+        let mut out = CheckpointWriter::new(TAG_XXH32);
+        out.put_u32(self.seed);
+        out.put_u64(self.total_len);
+        out.put_bool(self.large_len);
+        for lane in self.v {
+            out.put_u32(lane);
         }
+        out.put_u8(self.memsize as u8);
+        out.put_bytes(&self.mem32[..self.memsize]);
+        out.finish()
+    }
 
-        // Process remaining bytes
-        for &byte in remaining {
-            h = h.wrapping_add((byte as u32).wrapping_mul(XXH32_PRIME5));
-            h = rotl32(h, 11).wrapping_mul(XXH32_PRIME1);
+    /// Restore a state from `to_checkpoint` output
+    pub fn from_checkpoint(checkpoint: &[u8]) -> XXHashResult<Self> {
+        let mut input = CheckpointReader::new(checkpoint, TAG_XXH32)?;
+        let seed = input.get_u32()?;
+        let total_len = input.get_u64()?;
+        let large_len = input.get_bool()?;
+        let mut v = [0; 4];
+        for lane in &mut v {
+            *lane = input.get_u32()?;
         }
+        let memsize = input.get_u8()? as usize;
+        // The buffer always holds the tail of the input that does not fill a
+        // stripe, and large_len is set once the input reaches a whole stripe
+        if memsize as u64 != total_len % XXH32_STRIPE_LEN as u64 || large_len != (total_len >= XXH32_STRIPE_LEN as u64) {
+            return Err(XXHashError::InvalidState);
+        }
+        let mut mem32 = [0; XXH32_STRIPE_LEN];
+        mem32[..memsize].copy_from_slice(input.get_bytes(memsize)?);
+        input.finish()?;
+        Ok(Self { total_len, large_len, v, mem32, memsize, seed })
+    }
+}
 
-        // Avalanche
-        h ^= h >> 15;
-        h = h.wrapping_mul(XXH32_PRIME2);
-        h ^= h >> 13;
-        h = h.wrapping_mul(XXH32_PRIME3);
-        h ^= h >> 16;
+#[inline]
+pub(crate) const fn xxh32_round(acc: u32, input: u32) -> u32 {
+    let acc = acc.wrapping_add(input.wrapping_mul(XXH32_PRIME2));
+    rotl32(acc, 13).wrapping_mul(XXH32_PRIME1)
+}
 
-        h
+#[inline]
+pub(crate) const fn xxh32_init_lanes(seed: u32) -> [u32; 4] {
+    [
+        seed.wrapping_add(XXH32_PRIME1).wrapping_add(XXH32_PRIME2),
+        seed.wrapping_add(XXH32_PRIME2),
+        seed,
+        seed.wrapping_sub(XXH32_PRIME1),
+    ]
+}
+
+/// One round of each lane over a stripe
+#[inline(always)]
+fn xxh32_stripe(v: &mut [u32; 4], stripe: &Stripe) {
+    for (lane, word) in v.iter_mut().zip(stripe.chunks_exact(4)) {
+        *lane = xxh32_round(*lane, u32::from_le_bytes(word.try_into().unwrap()));
     }
+}
 
-    fn consume_buffer(&mut self) {
-        self.v[0] = xxh32_round(self.v[0], read_u32_le(&self.mem32[0..]));
-        self.v[1] = xxh32_round(self.v[1], read_u32_le(&self.mem32[4..]));
-        self.v[2] = xxh32_round(self.v[2], read_u32_le(&self.mem32[8..]));
-        self.v[3] = xxh32_round(self.v[3], read_u32_le(&self.mem32[12..]));
+/// Run every whole stripe of `input` through the lanes, returning the tail
+#[inline]
+fn xxh32_consume_stripes<'a>(v: &mut [u32; 4], input: &'a [u8]) -> &'a [u8] {
+    let mut stripes = input.chunks_exact(XXH32_STRIPE_LEN);
+    for stripe in &mut stripes {
+        xxh32_stripe(v, stripe.try_into().unwrap());
     }
+    stripes.remainder()
 }
 
 #[inline]
-fn xxh32_round(acc: u32, input: u32) -> u32 {
-    let acc = acc.wrapping_add(input.wrapping_mul(XXH32_PRIME2));
-    rotl32(acc, 13).wrapping_mul(XXH32_PRIME1)
+pub(crate) const fn xxh32_merge_lanes(v: &[u32; 4]) -> u32 {
+    rotl32(v[0], 1)
+        .wrapping_add(rotl32(v[1], 7))
+        .wrapping_add(rotl32(v[2], 12))
+        .wrapping_add(rotl32(v[3], 18))
 }
 
 /// Compute XXH32 hash with seed 0
@@ -142,62 +186,100 @@ pub fn xxh32(data: &[u8]) -> XXH32Hash {
 }
 
 /// Compute XXH32 hash with specified seed
+///
+/// Shares the stripe kernel and finalization with `XXH32State`.
 pub fn xxh32_with_seed(data: &[u8], seed: u32) -> XXH32Hash {
-    if data.len() < 16 {
-        xxh32_finalize(seed.wrapping_add(XXH32_PRIME5), data, data.len() as u64)
-    } else {
-        let mut v = [
-            seed.wrapping_add(XXH32_PRIME1).wrapping_add(XXH32_PRIME2),
-            seed.wrapping_add(XXH32_PRIME2),
-            seed,
-            seed.wrapping_sub(XXH32_PRIME1),
-        ];
-
-        let mut input = data;
-        while input.len() >= 16 {
-            v[0] = xxh32_round(v[0], read_u32_le(&input[0..]));
-            v[1] = xxh32_round(v[1], read_u32_le(&input[4..]));
-            v[2] = xxh32_round(v[2], read_u32_le(&input[8..]));
-            v[3] = xxh32_round(v[3], read_u32_le(&input[12..]));
-            input = &input[16..];
-        }
+    if data.len() < XXH32_STRIPE_LEN {
+        return xxh32_finalize(seed.wrapping_add(XXH32_PRIME5), data, data.len() as u64);
+    }
+    let mut v = xxh32_init_lanes(seed);
+    let remaining = xxh32_consume_stripes(&mut v, data);
+    xxh32_finalize(xxh32_merge_lanes(&v), remaining, data.len() as u64)
+}
 
-        let h = rotl32(v[0], 1)
-            .wrapping_add(rotl32(v[1], 7))
-            .wrapping_add(rotl32(v[2], 12))
-            .wrapping_add(rotl32(v[3], 18));
+/// Compute XXH32 of a `u32` key, equal to `xxh32(&key.to_le_bytes())`
+#[inline]
+pub fn xxh32_u32(key: u32) -> XXH32Hash {
+    xxh32_u32_with_seed(key, 0)
+}
 
-        xxh32_finalize(h, input, data.len() as u64)
-    }
+/// Compute XXH32 of a `u32` key with a seed
+///
+/// The key is shorter than a stripe, so this is the finalization alone with
+/// the length known at compile time.
+#[inline]
+pub fn xxh32_u32_with_seed(key: u32, seed: u32) -> XXH32Hash {
+    xxh32_finalize(seed.wrapping_add(XXH32_PRIME5), &key.to_le_bytes(), 4)
 }
 
 #[inline]
 fn xxh32_finalize(mut h: u32, remaining: &[u8], total_len: u64) -> u32 {
     h = h.wrapping_add(total_len as u32);
 
-    let mut input = remaining;
-    
     // Process 4-byte chunks
-    while input.len() >= 4 {
-        h = h.wrapping_add(read_u32_le(input).wrapping_mul(XXH32_PRIME3));
-        h = rotl32(h, 17).wrapping_mul(XXH32_PRIME4);
-        input = &input[4..];
+    let mut words = remaining.chunks_exact(4);
+    for word in &mut words {
+        h = xxh32_mix_word(h, u32::from_le_bytes(word.try_into().unwrap()));
     }
 
     // Process remaining bytes
-    for &byte in input {
-        h = h.wrapping_add((byte as u32).wrapping_mul(XXH32_PRIME5));
-        h = rotl32(h, 11).wrapping_mul(XXH32_PRIME1);
+    for &byte in words.remainder() {
+        h = xxh32_mix_byte(h, byte);
+    }
+
+    xxh32_avalanche(h)
+}
+
+/// Finalization step for a 4-byte word of the tail
+#[inline]
+pub(crate) const fn xxh32_mix_word(h: u32, word: u32) -> u32 {
+    rotl32(h.wrapping_add(word.wrapping_mul(XXH32_PRIME3)), 17).wrapping_mul(XXH32_PRIME4)
+}
+
+/// Finalization step for a single byte of the tail
+#[inline]
+pub(crate) const fn xxh32_mix_byte(h: u32, byte: u8) -> u32 {
+    rotl32(h.wrapping_add((byte as u32).wrapping_mul(XXH32_PRIME5)), 11).wrapping_mul(XXH32_PRIME1)
+}
+
+/// Canonical (big-endian) representation of an XXH32 hash - matches C XXH32_canonical_t
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct XXH32Canonical {
+    pub digest: [u8; 4],
+}
+
+impl XXH32Canonical {
+    /// Convert a hash to its canonical form
+    pub fn from_hash(hash: XXH32Hash) -> Self {
+        Self { digest: hash.to_be_bytes() }
     }
 
-    // Avalanche
-    h ^= h >> 15;
-    h = h.wrapping_mul(XXH32_PRIME2);
-    h ^= h >> 13;
-    h = h.wrapping_mul(XXH32_PRIME3);
-    h ^= h >> 16;
+    /// Convert the canonical form back to a hash
+    pub fn to_hash(&self) -> XXH32Hash {
+        XXH32Hash::from_be_bytes(self.digest)
+    }
 
-    h
+    /// Read a canonical hash from the start of a byte slice
+    pub fn from_slice(bytes: &[u8]) -> XXHashResult<Self> {
+        if bytes.len() < 4 {
+            return Err(XXHashError::BufferTooSmall { required: 4, available: bytes.len() });
+        }
+        let mut digest = [0u8; 4];
+        digest.copy_from_slice(&bytes[..4]);
+        Ok(Self { digest })
+    }
+}
+
+impl From<XXH32Hash> for XXH32Canonical {
+    fn from(hash: XXH32Hash) -> Self {
+        Self::from_hash(hash)
+    }
+}
+
+impl From<XXH32Canonical> for XXH32Hash {
+    fn from(canonical: XXH32Canonical) -> Self {
+        canonical.to_hash()
+    }
 }
 
 #[cfg(test)]
@@ -216,6 +298,15 @@ mod tests {
         assert_eq!(xxh32_with_seed(b"a", 0x12345678), 0x0d5a8e75);
     }
 
+    #[test]
+    fn test_xxh32_canonical() {
+        let canonical = XXH32Canonical::from_hash(xxh32(b"a"));
+        assert_eq!(canonical.digest, [0x55, 0x0d, 0x74, 0x56]);
+        assert_eq!(canonical.to_hash(), 0x550d7456);
+        assert_eq!(XXH32Canonical::from_slice(&[0x55, 0x0d, 0x74, 0x56, 0xff]), Ok(canonical));
+        assert!(XXH32Canonical::from_slice(&[0x55, 0x0d]).is_err());
+    }
+
     #[test]
     fn test_xxh32_streaming() {
         let mut state = XXH32State::new(0);
@@ -223,4 +314,18 @@ mod tests {
         state.update(b"o world").unwrap();
         assert_eq!(state.digest(), xxh32(b"hello world"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_streaming_matches_oneshot() {
+        let data: Vec<u8> = (0..300u32).map(|i| (i * 7 + 3) as u8).collect();
+        for len in 0..data.len() {
+            let expected = xxh32_with_seed(&data[..len], 0x5eed);
+            for split in [0, 1, len / 2, len.saturating_sub(1), len] {
+                let mut state = XXH32State::new(0x5eed);
+                state.update(&data[..split.min(len)]).unwrap();
+                state.update(&data[split.min(len)..len]).unwrap();
+                assert_eq!(state.digest(), expected, "len {}, split {}", len, split);
+            }
+        }
+    }
+}
@@ -0,0 +1,329 @@
+//! XXH3 stripe kernels: scalar, SSE2 and AVX2, selected at runtime
+//!
+//! Only the accumulate and scramble steps of long inputs are vectorised;
+//! every backend produces bit-identical accumulators. Without the `std`
+//! feature there is no runtime CPU detection, and the vector backends are
+//! only used when the target enables them at compile time.
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use crate::constants::*;
+use crate::error::{XXHashError, XXHashResult};
+
+/// Implementation of the XXH3 accumulate/scramble kernels
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum XXH3Backend {
+    /// Portable 64-bit arithmetic
+    Scalar,
+    /// 128-bit SSE2 vectors (x86/x86_64)
+    Sse2,
+    /// 256-bit AVX2 vectors (x86/x86_64)
+    Avx2,
+}
+
+const UNSET: u8 = 0;
+
+impl XXH3Backend {
+    /// All backends, slowest first
+    pub const ALL: [XXH3Backend; 3] = [XXH3Backend::Scalar, XXH3Backend::Sse2, XXH3Backend::Avx2];
+
+    /// Whether the running CPU can execute this backend
+    pub fn is_supported(self) -> bool {
+        match self {
+            XXH3Backend::Scalar => true,
+            #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), feature = "std"))]
+            XXH3Backend::Sse2 => is_x86_feature_detected!("sse2"),
+            #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), feature = "std"))]
+            XXH3Backend::Avx2 => is_x86_feature_detected!("avx2"),
+            #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), not(feature = "std")))]
+            XXH3Backend::Sse2 => cfg!(target_feature = "sse2"),
+            #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), not(feature = "std")))]
+            XXH3Backend::Avx2 => cfg!(target_feature = "avx2"),
+            #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+            XXH3Backend::Sse2 | XXH3Backend::Avx2 => false,
+        }
+    }
+
+    /// Fastest backend supported by the running CPU
+    pub fn detect() -> Self {
+        static DETECTED: AtomicU8 = AtomicU8::new(UNSET);
+        match Self::from_tag(DETECTED.load(Ordering::Relaxed)) {
+            Some(backend) => backend,
+            None => {
+                let backend = Self::ALL.into_iter().rev().find(|backend| backend.is_supported()).unwrap_or(XXH3Backend::Scalar);
+                DETECTED.store(backend.tag(), Ordering::Relaxed);
+                backend
+            }
+        }
+    }
+
+    /// Backend used for hashing: the forced one if set, else `detect()`
+    pub fn active() -> Self {
+        Self::from_tag(FORCED.load(Ordering::Relaxed)).unwrap_or_else(Self::detect)
+    }
+
+    fn tag(self) -> u8 {
+        match self {
+            XXH3Backend::Scalar => 1,
+            XXH3Backend::Sse2 => 2,
+            XXH3Backend::Avx2 => 3,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            1 => Some(XXH3Backend::Scalar),
+            2 => Some(XXH3Backend::Sse2),
+            3 => Some(XXH3Backend::Avx2),
+            _ => None,
+        }
+    }
+}
+
+static FORCED: AtomicU8 = AtomicU8::new(UNSET);
+
+/// Force every XXH3 hash in the process onto `backend`, or back to runtime
+/// detection with `None`
+///
+/// Meant for testing and benchmarking; the output never depends on the
+/// backend. Fails with `BackendUnavailable` if the CPU does not support
+/// the requested backend. A hash already in progress keeps the backend it
+/// started with until its current call returns.
+pub fn force_xxh3_backend(backend: Option<XXH3Backend>) -> XXHashResult<()> {
+    match backend {
+        Some(backend) if !backend.is_supported() => Err(XXHashError::BackendUnavailable(backend)),
+        Some(backend) => {
+            FORCED.store(backend.tag(), Ordering::Relaxed);
+            Ok(())
+        }
+        None => {
+            FORCED.store(UNSET, Ordering::Relaxed);
+            Ok(())
+        }
+    }
+}
+
+/// Kernels of the backend active when a hash or update call started
+///
+/// Resolved once per call instead of once per block. Only `active` can make
+/// one, so a vector backend here is always supported by the CPU.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct XXH3Kernels {
+    backend: XXH3Backend,
+}
+
+impl XXH3Kernels {
+    pub(crate) fn active() -> Self {
+        Self { backend: XXH3Backend::active() }
+    }
+
+    /// Accumulate `nb_stripes` consecutive stripes, advancing the secret by
+    /// XXH3_SECRET_CONSUME_RATE each time
+    #[inline]
+    pub(crate) fn accumulate(self, acc: &mut [u64; XXH3_ACC_NB], input: &[u8], secret: &[u8], nb_stripes: usize) {
+        if nb_stripes == 0 {
+            return;
+        }
+        match self.backend {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            XXH3Backend::Avx2 | XXH3Backend::Sse2 => {
+                // Keeps the unchecked vector loads in bounds
+                assert!(
+                    input.len() >= nb_stripes * XXH3_STRIPE_LEN
+                        && secret.len() >= (nb_stripes - 1) * XXH3_SECRET_CONSUME_RATE + XXH3_STRIPE_LEN
+                );
+                // SAFETY: the backend came from XXH3Backend::active, which only
+                // returns supported backends, and the lengths were checked above
+                unsafe {
+                    if self.backend == XXH3Backend::Avx2 {
+                        x86::accumulate_avx2(acc, input, secret, nb_stripes)
+                    } else {
+                        x86::accumulate_sse2(acc, input, secret, nb_stripes)
+                    }
+                }
+            }
+            _ => scalar::accumulate(acc, input, secret, nb_stripes),
+        }
+    }
+
+    /// Scramble the accumulators at the end of each block
+    #[inline]
+    pub(crate) fn scramble_acc(self, acc: &mut [u64; XXH3_ACC_NB], secret: &[u8]) {
+        match self.backend {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            XXH3Backend::Avx2 | XXH3Backend::Sse2 => {
+                assert!(secret.len() >= XXH3_STRIPE_LEN);
+                // SAFETY: as in accumulate
+                unsafe {
+                    if self.backend == XXH3Backend::Avx2 {
+                        x86::scramble_avx2(acc, secret)
+                    } else {
+                        x86::scramble_sse2(acc, secret)
+                    }
+                }
+            }
+            _ => scalar::scramble(acc, secret),
+        }
+    }
+}
+
+pub(crate) mod scalar {
+    use crate::constants::*;
+
+    type Stripe = [u8; XXH3_STRIPE_LEN];
+
+    #[inline(always)]
+    fn lane(bytes: &Stripe, i: usize) -> u64 {
+        let mut word = [0u8; 8];
+        word.copy_from_slice(&bytes[8 * i..8 * i + 8]);
+        u64::from_le_bytes(word)
+    }
+
+    /// Fixed-size stripe view, so the lane reads need no bounds checks
+    #[inline(always)]
+    fn stripe(bytes: &[u8]) -> &Stripe {
+        bytes[..XXH3_STRIPE_LEN].try_into().unwrap()
+    }
+
+    /// One lane of XXH3_accumulate_512, also used by the `const fn` hashes
+    #[inline(always)]
+    pub(crate) const fn accumulate_lane(acc: &mut [u64; XXH3_ACC_NB], i: usize, data_val: u64, key: u64) {
+        let data_key = data_val ^ key;
+        acc[i ^ 1] = acc[i ^ 1].wrapping_add(data_val);
+        acc[i] = acc[i].wrapping_add((data_key & 0xFFFF_FFFF).wrapping_mul(data_key >> 32));
+    }
+
+    /// One lane of XXH3_scrambleAcc, also used by the `const fn` hashes
+    #[inline(always)]
+    pub(crate) const fn scramble_lane(acc64: u64, key: u64) -> u64 {
+        (acc64 ^ (acc64 >> 47) ^ key).wrapping_mul(XXH32_PRIME1 as u64)
+    }
+
+    #[inline(always)]
+    fn accumulate_512(acc: &mut [u64; XXH3_ACC_NB], input: &Stripe, secret: &Stripe) {
+        for i in 0..XXH3_ACC_NB {
+            accumulate_lane(acc, i, lane(input, i), lane(secret, i));
+        }
+    }
+
+    pub(super) fn accumulate(acc: &mut [u64; XXH3_ACC_NB], input: &[u8], secret: &[u8], nb_stripes: usize) {
+        for n in 0..nb_stripes {
+            accumulate_512(
+                acc,
+                stripe(&input[n * XXH3_STRIPE_LEN..]),
+                stripe(&secret[n * XXH3_SECRET_CONSUME_RATE..]),
+            );
+        }
+    }
+
+    pub(super) fn scramble(acc: &mut [u64; XXH3_ACC_NB], secret: &[u8]) {
+        let secret = stripe(secret);
+        for (i, acc_lane) in acc.iter_mut().enumerate() {
+            *acc_lane = scramble_lane(*acc_lane, lane(secret, i));
+        }
+    }
+}
+
+/// Vector kernels, following XXH3_accumulate_512_sse2/avx2 and
+/// XXH3_scrambleAcc_sse2/avx2 of the C library
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod x86 {
+    #[cfg(target_arch = "x86")]
+    use core::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use core::arch::x86_64::*;
+
+    use crate::constants::*;
+
+    /// _MM_SHUFFLE(0, 3, 0, 1): move the high 32 bits of each lane down
+    const SHUFFLE_HI_TO_LO: i32 = 0b00_11_00_01;
+    /// _MM_SHUFFLE(1, 0, 3, 2): swap the two 64-bit halves
+    const SHUFFLE_SWAP_64: i32 = 0b01_00_11_10;
+
+    /// # Safety
+    /// The CPU must support SSE2; `input` must hold `nb_stripes` stripes and
+    /// `secret` the matching `(nb_stripes - 1) * 8 + 64` bytes.
+    #[target_feature(enable = "sse2")]
+    pub(super) unsafe fn accumulate_sse2(acc: &mut [u64; XXH3_ACC_NB], input: &[u8], secret: &[u8], nb_stripes: usize) {
+        let acc_ptr = acc.as_mut_ptr() as *mut __m128i;
+        let mut lanes = [_mm_setzero_si128(); 4];
+        for (i, lane) in lanes.iter_mut().enumerate() {
+            *lane = _mm_loadu_si128(acc_ptr.add(i));
+        }
+        for n in 0..nb_stripes {
+            let data_ptr = input.as_ptr().add(n * XXH3_STRIPE_LEN) as *const __m128i;
+            let key_ptr = secret.as_ptr().add(n * XXH3_SECRET_CONSUME_RATE) as *const __m128i;
+            for (i, lane) in lanes.iter_mut().enumerate() {
+                let data_vec = _mm_loadu_si128(data_ptr.add(i));
+                let key_vec = _mm_loadu_si128(key_ptr.add(i));
+                let data_key = _mm_xor_si128(data_vec, key_vec);
+                let data_key_lo = _mm_shuffle_epi32::<SHUFFLE_HI_TO_LO>(data_key);
+                let product = _mm_mul_epu32(data_key, data_key_lo);
+                let data_swap = _mm_shuffle_epi32::<SHUFFLE_SWAP_64>(data_vec);
+                *lane = _mm_add_epi64(product, _mm_add_epi64(*lane, data_swap));
+            }
+        }
+        for (i, lane) in lanes.iter().enumerate() {
+            _mm_storeu_si128(acc_ptr.add(i), *lane);
+        }
+    }
+
+    /// # Safety
+    /// The CPU must support SSE2; `secret` must hold at least 64 bytes.
+    #[target_feature(enable = "sse2")]
+    pub(super) unsafe fn scramble_sse2(acc: &mut [u64; XXH3_ACC_NB], secret: &[u8]) {
+        let acc_ptr = acc.as_mut_ptr() as *mut __m128i;
+        let key_ptr = secret.as_ptr() as *const __m128i;
+        let prime32 = _mm_set1_epi32(XXH32_PRIME1 as i32);
+        for i in 0..XXH3_STRIPE_LEN / 16 {
+            let acc_vec = _mm_loadu_si128(acc_ptr.add(i));
+            let data_vec = _mm_xor_si128(acc_vec, _mm_srli_epi64::<47>(acc_vec));
+            let data_key = _mm_xor_si128(data_vec, _mm_loadu_si128(key_ptr.add(i)));
+            let data_key_hi = _mm_shuffle_epi32::<SHUFFLE_HI_TO_LO>(data_key);
+            let prod_lo = _mm_mul_epu32(data_key, prime32);
+            let prod_hi = _mm_mul_epu32(data_key_hi, prime32);
+            _mm_storeu_si128(acc_ptr.add(i), _mm_add_epi64(prod_lo, _mm_slli_epi64::<32>(prod_hi)));
+        }
+    }
+
+    /// # Safety
+    /// The CPU must support AVX2; same length requirements as `accumulate_sse2`.
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn accumulate_avx2(acc: &mut [u64; XXH3_ACC_NB], input: &[u8], secret: &[u8], nb_stripes: usize) {
+        let acc_ptr = acc.as_mut_ptr() as *mut __m256i;
+        let mut lanes = [_mm256_loadu_si256(acc_ptr), _mm256_loadu_si256(acc_ptr.add(1))];
+        for n in 0..nb_stripes {
+            let data_ptr = input.as_ptr().add(n * XXH3_STRIPE_LEN) as *const __m256i;
+            let key_ptr = secret.as_ptr().add(n * XXH3_SECRET_CONSUME_RATE) as *const __m256i;
+            for (i, lane) in lanes.iter_mut().enumerate() {
+                let data_vec = _mm256_loadu_si256(data_ptr.add(i));
+                let key_vec = _mm256_loadu_si256(key_ptr.add(i));
+                let data_key = _mm256_xor_si256(data_vec, key_vec);
+                let data_key_lo = _mm256_shuffle_epi32::<SHUFFLE_HI_TO_LO>(data_key);
+                let product = _mm256_mul_epu32(data_key, data_key_lo);
+                let data_swap = _mm256_shuffle_epi32::<SHUFFLE_SWAP_64>(data_vec);
+                *lane = _mm256_add_epi64(product, _mm256_add_epi64(*lane, data_swap));
+            }
+        }
+        _mm256_storeu_si256(acc_ptr, lanes[0]);
+        _mm256_storeu_si256(acc_ptr.add(1), lanes[1]);
+    }
+
+    /// # Safety
+    /// The CPU must support AVX2; `secret` must hold at least 64 bytes.
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn scramble_avx2(acc: &mut [u64; XXH3_ACC_NB], secret: &[u8]) {
+        let acc_ptr = acc.as_mut_ptr() as *mut __m256i;
+        let key_ptr = secret.as_ptr() as *const __m256i;
+        let prime32 = _mm256_set1_epi32(XXH32_PRIME1 as i32);
+        for i in 0..XXH3_STRIPE_LEN / 32 {
+            let acc_vec = _mm256_loadu_si256(acc_ptr.add(i));
+            let data_vec = _mm256_xor_si256(acc_vec, _mm256_srli_epi64::<47>(acc_vec));
+            let data_key = _mm256_xor_si256(data_vec, _mm256_loadu_si256(key_ptr.add(i)));
+            let data_key_hi = _mm256_shuffle_epi32::<SHUFFLE_HI_TO_LO>(data_key);
+            let prod_lo = _mm256_mul_epu32(data_key, prime32);
+            let prod_hi = _mm256_mul_epu32(data_key_hi, prime32);
+            _mm256_storeu_si256(acc_ptr.add(i), _mm256_add_epi64(prod_lo, _mm256_slli_epi64::<32>(prod_hi)));
+        }
+    }
+}
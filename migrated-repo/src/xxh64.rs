@@ -1,18 +1,28 @@
 //! XXH64 hash algorithm implementation
 
+#[cfg(feature = "alloc")]
+use crate::checkpoint::CheckpointWriter;
+use crate::checkpoint::{CheckpointReader, TAG_XXH64};
 use crate::constants::*;
-use crate::error::XXHashResult;
+use crate::error::{XXHashError, XXHashResult};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
 
 /// XXH64 hash type
 pub type XXH64Hash = u64;
 
+/// Bytes consumed per round of the four lanes
+const XXH64_STRIPE_LEN: usize = 32;
+
+type Stripe = [u8; XXH64_STRIPE_LEN];
+
 /// XXH64 streaming state
 #[derive(Debug, Clone)]
 pub struct XXH64State {
     total_len: u64,
     large_len: bool,
     v: [u64; 4],
-    mem64: [u8; 32],
+    mem64: [u8; XXH64_STRIPE_LEN],
     memsize: usize,
     seed: u64,
 }
@@ -23,18 +33,18 @@ impl XXH64State {
         Self {
             total_len: 0,
             large_len: false,
-            v: [
-                seed.wrapping_add(XXH64_PRIME1).wrapping_add(XXH64_PRIME2),
-                seed.wrapping_add(XXH64_PRIME2),
-                seed,
-                seed.wrapping_sub(XXH64_PRIME1),
-            ],
-            mem64: [0; 32],
+            v: xxh64_init_lanes(seed),
+            mem64: [0; XXH64_STRIPE_LEN],
             memsize: 0,
             seed,
         }
     }
 
+    /// Seed this state was created with
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
     /// Reset state with new seed
     pub fn reset(&mut self, seed: u64) -> XXHashResult<()> {
         *self = Self::new(seed);
@@ -44,13 +54,13 @@ impl XXH64State {
     /// Update hash with new data
     pub fn update(&mut self, data: &[u8]) -> XXHashResult<()> {
         self.total_len = self.total_len.wrapping_add(data.len() as u64);
-        self.large_len |= (data.len() >= 32) | (self.total_len >= 32);
+        self.large_len |= (data.len() >= XXH64_STRIPE_LEN) | (self.total_len >= XXH64_STRIPE_LEN as u64);
 
         let mut input = data;
 
         // Fill buffer if we have leftover data
         if self.memsize > 0 {
-            let to_fill = 32 - self.memsize;
+            let to_fill = XXH64_STRIPE_LEN - self.memsize;
             if input.len() < to_fill {
                 // Not enough data to fill buffer
                 self.mem64[self.memsize..self.memsize + input.len()].copy_from_slice(input);
@@ -59,20 +69,14 @@ impl XXH64State {
             }
 
             // Fill buffer and process
-            self.mem64[self.memsize..32].copy_from_slice(&input[..to_fill]);
-            self.consume_buffer();
+            self.mem64[self.memsize..].copy_from_slice(&input[..to_fill]);
+            xxh64_stripe(&mut self.v, &self.mem64);
             input = &input[to_fill..];
             self.memsize = 0;
         }
 
-        // Process complete 32-byte chunks
-        while input.len() >= 32 {
-            self.v[0] = xxh64_round(self.v[0], read_u64_le(&input[0..]));
-            self.v[1] = xxh64_round(self.v[1], read_u64_le(&input[8..]));
-            self.v[2] = xxh64_round(self.v[2], read_u64_le(&input[16..]));
-            self.v[3] = xxh64_round(self.v[3], read_u64_le(&input[24..]));
-            input = &input[32..];
-        }
+        // Process complete stripes
+        let input = xxh64_consume_stripes(&mut self.v, input);
 
         // Store remaining data in buffer
         if !input.is_empty() {
@@ -85,76 +89,104 @@ impl XXH64State {
 
     /// Finalize and get hash
     pub fn digest(&self) -> XXH64Hash {
-        let mut h = if self.large_len {
-            let mut h = rotl64(self.v[0], 1)
-                .wrapping_add(rotl64(self.v[1], 7))
-                .wrapping_add(rotl64(self.v[2], 12))
-                .wrapping_add(rotl64(self.v[3], 18));
-
-            h = xxh64_merge_round(h, self.v[0]);
-            h = xxh64_merge_round(h, self.v[1]);
-            h = xxh64_merge_round(h, self.v[2]);
-            h = xxh64_merge_round(h, self.v[3]);
-            h
+        let h = if self.large_len {
+            xxh64_merge_lanes(&self.v)
         } else {
             self.seed.wrapping_add(XXH64_PRIME5)
         };
+        xxh64_finalize(h, &self.mem64[..self.memsize], self.total_len)
+    }
 
-        h = h.wrapping_add(self.total_len);
-
-        // Process remaining bytes
-        let mut remaining = &self.mem64[..self.memsize];
-        
-        // Process 8-byte chunks
-        while remaining.len() >= 8 {
-            let k1 = xxh64_round(0, read_u64_le(remaining));
-            h ^= k1;
-            h = rotl64(h, 27).wrapping_mul(XXH64_PRIME1).wrapping_add(XXH64_PRIME4);
-            remaining = &remaining[8..];
+    /// Serialize the full state into a versioned checkpoint
+    ///
+    /// `from_checkpoint` restores a state that continues exactly where this
+    /// one stopped, in any process.
+    #[cfg(feature = "alloc")]
+    pub fn to_checkpoint(&self) -> Vec<u8> {
+        let mut out = CheckpointWriter::new(TAG_XXH64);
+        out.put_u64(self.seed);
+        out.put_u64(self.total_len);
+        out.put_bool(self.large_len);
+        for lane in self.v {
+            out.put_u64(lane);
         }
+        out.put_u8(self.memsize as u8);
+        out.put_bytes(&self.mem64[..self.memsize]);
+        out.finish()
+    }
 
-        // Process 4-byte chunk
-        if remaining.len() >= 4 {
-            h ^= (read_u32_le(remaining) as u64).wrapping_mul(XXH64_PRIME1);
-            h = rotl64(h, 23).wrapping_mul(XXH64_PRIME2).wrapping_add(XXH64_PRIME3);
-            remaining = &remaining[4..];
+    /// Restore a state from `to_checkpoint` output
+    pub fn from_checkpoint(checkpoint: &[u8]) -> XXHashResult<Self> {
+        let mut input = CheckpointReader::new(checkpoint, TAG_XXH64)?;
+        let seed = input.get_u64()?;
+        let total_len = input.get_u64()?;
+        let large_len = input.get_bool()?;
+        let mut v = [0; 4];
+        for lane in &mut v {
+            *lane = input.get_u64()?;
         }
-
-        // Process remaining bytes
-        for &byte in remaining {
-            h ^= (byte as u64).wrapping_mul(XXH64_PRIME5);
-            h = rotl64(h, 11).wrapping_mul(XXH64_PRIME1);
+        let memsize = input.get_u8()? as usize;
+        // The buffer always holds the tail of the input that does not fill a
+        // stripe, and large_len is set once the input reaches a whole stripe
+        if memsize as u64 != total_len % XXH64_STRIPE_LEN as u64 || large_len != (total_len >= XXH64_STRIPE_LEN as u64) {
+            return Err(XXHashError::InvalidState);
         }
+        let mut mem64 = [0; XXH64_STRIPE_LEN];
+        mem64[..memsize].copy_from_slice(input.get_bytes(memsize)?);
+        input.finish()?;
+        Ok(Self { total_len, large_len, v, mem64, memsize, seed })
+    }
+}
+
+#[inline]
+pub(crate) const fn xxh64_round(acc: u64, input: u64) -> u64 {
+    let acc = acc.wrapping_add(input.wrapping_mul(XXH64_PRIME2));
+    rotl64(acc, 31).wrapping_mul(XXH64_PRIME1)
+}
 
-        // Avalanche
-        h ^= h >> 33;
-        h = h.wrapping_mul(XXH64_PRIME2);
-        h ^= h >> 29;
-        h = h.wrapping_mul(XXH64_PRIME3);
-        h ^= h >> 32;
+#[inline]
+pub(crate) const fn xxh64_init_lanes(seed: u64) -> [u64; 4] {
+    [
+        seed.wrapping_add(XXH64_PRIME1).wrapping_add(XXH64_PRIME2),
+        seed.wrapping_add(XXH64_PRIME2),
+        seed,
+        seed.wrapping_sub(XXH64_PRIME1),
+    ]
+}
 
-        h
+/// One round of each lane over a stripe
+#[inline(always)]
+fn xxh64_stripe(v: &mut [u64; 4], stripe: &Stripe) {
+    for (lane, word) in v.iter_mut().zip(stripe.chunks_exact(8)) {
+        *lane = xxh64_round(*lane, u64::from_le_bytes(word.try_into().unwrap()));
     }
+}
 
-    fn consume_buffer(&mut self) {
-        self.v[0] = xxh64_round(self.v[0], read_u64_le(&self.mem64[0..]));
-        self.v[1] = xxh64_round(self.v[1], read_u64_le(&self.mem64[8..]));
-        self.v[2] = xxh64_round(self.v[2], read_u64_le(&self.mem64[16..]));
-        self.v[3] = xxh64_round(self.v[3], read_u64_le(&self.mem64[24..]));
+/// Run every whole stripe of `input` through the lanes, returning the tail
+#[inline]
+fn xxh64_consume_stripes<'a>(v: &mut [u64; 4], input: &'a [u8]) -> &'a [u8] {
+    let mut stripes = input.chunks_exact(XXH64_STRIPE_LEN);
+    for stripe in &mut stripes {
+        xxh64_stripe(v, stripe.try_into().unwrap());
     }
+    stripes.remainder()
 }
 
 #[inline]
-fn xxh64_round(acc: u64, input: u64) -> u64 {
-    let acc = acc.wrapping_add(input.wrapping_mul(XXH64_PRIME2));
-    rotl64(acc, 31).wrapping_mul(XXH64_PRIME1)
+pub(crate) const fn xxh64_merge_lanes(v: &[u64; 4]) -> u64 {
+    let h = rotl64(v[0], 1)
+        .wrapping_add(rotl64(v[1], 7))
+        .wrapping_add(rotl64(v[2], 12))
+        .wrapping_add(rotl64(v[3], 18));
+    let h = xxh64_merge_round(h, v[0]);
+    let h = xxh64_merge_round(h, v[1]);
+    let h = xxh64_merge_round(h, v[2]);
+    xxh64_merge_round(h, v[3])
 }
 
 #[inline]
-fn xxh64_merge_round(mut acc: u64, val: u64) -> u64 {
-    let val = xxh64_round(0, val);
-    acc ^= val;
-    acc.wrapping_mul(XXH64_PRIME1).wrapping_add(XXH64_PRIME4)
+pub(crate) const fn xxh64_merge_round(acc: u64, val: u64) -> u64 {
+    (acc ^ xxh64_round(0, val)).wrapping_mul(XXH64_PRIME1).wrapping_add(XXH64_PRIME4)
 }
 
 /// Compute XXH64 hash with seed 0
@@ -163,75 +195,113 @@ pub fn xxh64(data: &[u8]) -> XXH64Hash {
 }
 
 /// Compute XXH64 hash with specified seed
+///
+/// Shares the stripe kernel and finalization with `XXH64State`.
 pub fn xxh64_with_seed(data: &[u8], seed: u64) -> XXH64Hash {
-    if data.len() < 32 {
-        xxh64_finalize(seed.wrapping_add(XXH64_PRIME5), data, data.len() as u64)
-    } else {
-        let mut v = [
-            seed.wrapping_add(XXH64_PRIME1).wrapping_add(XXH64_PRIME2),
-            seed.wrapping_add(XXH64_PRIME2),
-            seed,
-            seed.wrapping_sub(XXH64_PRIME1),
-        ];
-
-        let mut input = data;
-        while input.len() >= 32 {
-            v[0] = xxh64_round(v[0], read_u64_le(&input[0..]));
-            v[1] = xxh64_round(v[1], read_u64_le(&input[8..]));
-            v[2] = xxh64_round(v[2], read_u64_le(&input[16..]));
-            v[3] = xxh64_round(v[3], read_u64_le(&input[24..]));
-            input = &input[32..];
-        }
-
-        let mut h = rotl64(v[0], 1)
-            .wrapping_add(rotl64(v[1], 7))
-            .wrapping_add(rotl64(v[2], 12))
-            .wrapping_add(rotl64(v[3], 18));
+    if data.len() < XXH64_STRIPE_LEN {
+        return xxh64_finalize(seed.wrapping_add(XXH64_PRIME5), data, data.len() as u64);
+    }
+    let mut v = xxh64_init_lanes(seed);
+    let remaining = xxh64_consume_stripes(&mut v, data);
+    xxh64_finalize(xxh64_merge_lanes(&v), remaining, data.len() as u64)
+}
 
-        h = xxh64_merge_round(h, v[0]);
-        h = xxh64_merge_round(h, v[1]);
-        h = xxh64_merge_round(h, v[2]);
-        h = xxh64_merge_round(h, v[3]);
+/// Compute XXH64 of a `u64` key, equal to `xxh64(&key.to_le_bytes())`
+#[inline]
+pub fn xxh64_u64(key: u64) -> XXH64Hash {
+    xxh64_u64_with_seed(key, 0)
+}
 
-        xxh64_finalize(h, input, data.len() as u64)
-    }
+/// Compute XXH64 of a `u64` key with a seed
+///
+/// The key is shorter than a stripe, so this is the finalization alone with
+/// the length known at compile time.
+#[inline]
+pub fn xxh64_u64_with_seed(key: u64, seed: u64) -> XXH64Hash {
+    xxh64_finalize(seed.wrapping_add(XXH64_PRIME5), &key.to_le_bytes(), 8)
 }
 
 #[inline]
 fn xxh64_finalize(mut h: u64, remaining: &[u8], total_len: u64) -> u64 {
     h = h.wrapping_add(total_len);
 
-    let mut input = remaining;
-    
     // Process 8-byte chunks
-    while input.len() >= 8 {
-        let k1 = xxh64_round(0, read_u64_le(input));
-        h ^= k1;
-        h = rotl64(h, 27).wrapping_mul(XXH64_PRIME1).wrapping_add(XXH64_PRIME4);
-        input = &input[8..];
+    let mut words = remaining.chunks_exact(8);
+    for word in &mut words {
+        h = xxh64_mix_word(h, u64::from_le_bytes(word.try_into().unwrap()));
     }
 
     // Process 4-byte chunk
+    let mut input = words.remainder();
     if input.len() >= 4 {
-        h ^= (read_u32_le(input) as u64).wrapping_mul(XXH64_PRIME1);
-        h = rotl64(h, 23).wrapping_mul(XXH64_PRIME2).wrapping_add(XXH64_PRIME3);
+        h = xxh64_mix_half(h, read_u32_le(input));
         input = &input[4..];
     }
 
     // Process remaining bytes
     for &byte in input {
-        h ^= (byte as u64).wrapping_mul(XXH64_PRIME5);
-        h = rotl64(h, 11).wrapping_mul(XXH64_PRIME1);
+        h = xxh64_mix_byte(h, byte);
     }
 
-    // Avalanche
-    h ^= h >> 33;
-    h = h.wrapping_mul(XXH64_PRIME2);
-    h ^= h >> 29;
-    h = h.wrapping_mul(XXH64_PRIME3);
-    h ^= h >> 32;
+    xxh64_avalanche(h)
+}
 
-    h
+/// Finalization step for an 8-byte word of the tail
+#[inline]
+pub(crate) const fn xxh64_mix_word(h: u64, word: u64) -> u64 {
+    rotl64(h ^ xxh64_round(0, word), 27).wrapping_mul(XXH64_PRIME1).wrapping_add(XXH64_PRIME4)
+}
+
+/// Finalization step for the 4-byte word left after the 8-byte words
+#[inline]
+pub(crate) const fn xxh64_mix_half(h: u64, half: u32) -> u64 {
+    rotl64(h ^ (half as u64).wrapping_mul(XXH64_PRIME1), 23).wrapping_mul(XXH64_PRIME2).wrapping_add(XXH64_PRIME3)
+}
+
+/// Finalization step for a single byte of the tail
+#[inline]
+pub(crate) const fn xxh64_mix_byte(h: u64, byte: u8) -> u64 {
+    rotl64(h ^ (byte as u64).wrapping_mul(XXH64_PRIME5), 11).wrapping_mul(XXH64_PRIME1)
+}
+
+/// Canonical (big-endian) representation of an XXH64 hash - matches C XXH64_canonical_t
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct XXH64Canonical {
+    pub digest: [u8; 8],
+}
+
+impl XXH64Canonical {
+    /// Convert a hash to its canonical form
+    pub fn from_hash(hash: XXH64Hash) -> Self {
+        Self { digest: hash.to_be_bytes() }
+    }
+
+    /// Convert the canonical form back to a hash
+    pub fn to_hash(&self) -> XXH64Hash {
+        XXH64Hash::from_be_bytes(self.digest)
+    }
+
+    /// Read a canonical hash from the start of a byte slice
+    pub fn from_slice(bytes: &[u8]) -> XXHashResult<Self> {
+        if bytes.len() < 8 {
+            return Err(XXHashError::BufferTooSmall { required: 8, available: bytes.len() });
+        }
+        let mut digest = [0u8; 8];
+        digest.copy_from_slice(&bytes[..8]);
+        Ok(Self { digest })
+    }
+}
+
+impl From<XXH64Hash> for XXH64Canonical {
+    fn from(hash: XXH64Hash) -> Self {
+        Self::from_hash(hash)
+    }
+}
+
+impl From<XXH64Canonical> for XXH64Hash {
+    fn from(canonical: XXH64Canonical) -> Self {
+        canonical.to_hash()
+    }
 }
 
 #[cfg(test)]
@@ -250,6 +320,17 @@ mod tests {
         assert_eq!(xxh64_with_seed(b"a", 0x123456789abcdef0), 0x9e29aa7f69e1808f);
     }
 
+    #[test]
+    fn test_xxh64_canonical() {
+        let canonical: XXH64Canonical = xxh64(b"a").into();
+        assert_eq!(canonical.digest, [0xd2, 0x4e, 0xc4, 0xf1, 0xa9, 0x8c, 0x6e, 0x5b]);
+        assert_eq!(XXH64Hash::from(canonical), 0xd24ec4f1a98c6e5b);
+        assert_eq!(
+            XXH64Canonical::from_slice(&[0u8; 4]),
+            Err(XXHashError::BufferTooSmall { required: 8, available: 4 })
+        );
+    }
+
     #[test]
     fn test_xxh64_streaming() {
         let mut state = XXH64State::new(0);
@@ -257,4 +338,18 @@ mod tests {
         state.update(b"o world").unwrap();
         assert_eq!(state.digest(), xxh64(b"hello world"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_streaming_matches_oneshot() {
+        let data: Vec<u8> = (0..300u32).map(|i| (i * 7 + 3) as u8).collect();
+        for len in 0..data.len() {
+            let expected = xxh64_with_seed(&data[..len], 0x5eed);
+            for split in [0, 1, len / 2, len.saturating_sub(1), len] {
+                let mut state = XXH64State::new(0x5eed);
+                state.update(&data[..split.min(len)]).unwrap();
+                state.update(&data[split.min(len)..len]).unwrap();
+                assert_eq!(state.digest(), expected, "len {}, split {}", len, split);
+            }
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! Checkpoint/restore round trips for the streaming states
+
+#![cfg(feature = "alloc")]
+
+use xxhash_migration::checkpoint::{CHECKPOINT_MAGIC, CHECKPOINT_VERSION};
+use xxhash_migration::*;
+
+mod common;
+use common::fill_test_buffer;
+
+/// Replace the trailing checksum after editing a field, so the field checks
+/// are what rejects the checkpoint
+fn reseal(mut checkpoint: Vec<u8>) -> Vec<u8> {
+    checkpoint.truncate(checkpoint.len() - 8);
+    let checksum = xxh64(&checkpoint);
+    checkpoint.extend_from_slice(&checksum.to_le_bytes());
+    checkpoint
+}
+
+const SPLITS: &[usize] = &[0, 1, 15, 16, 17, 31, 32, 33, 240, 255, 256, 257, 1000, 1024, 4095, 4096, 4097, 9999];
+
+#[test]
+fn test_xxh32_resume() {
+    let data = fill_test_buffer(10_000);
+    for &split in SPLITS {
+        let mut state = XXH32State::new(0x12345678);
+        state.update(&data[..split]).unwrap();
+        let mut resumed = XXH32State::from_checkpoint(&state.to_checkpoint()).unwrap();
+        assert_eq!(resumed.digest(), state.digest(), "split {}", split);
+        resumed.update(&data[split..]).unwrap();
+        assert_eq!(resumed.digest(), xxh32_with_seed(&data, 0x12345678), "split {}", split);
+    }
+}
+
+#[test]
+fn test_xxh64_resume() {
+    let data = fill_test_buffer(10_000);
+    for &split in SPLITS {
+        let mut state = XXH64State::new(0x123456789abcdef0);
+        state.update(&data[..split]).unwrap();
+        let mut resumed = XXH64State::from_checkpoint(&state.to_checkpoint()).unwrap();
+        resumed.update(&data[split..]).unwrap();
+        assert_eq!(resumed.digest(), xxh64_with_seed(&data, 0x123456789abcdef0), "split {}", split);
+    }
+}
+
+#[test]
+fn test_xxh3_resume() {
+    let data = fill_test_buffer(10_000);
+    let secret = generate_secret_from_seed(77);
+    let secret = &secret[..150];
+    let seed = 0x9e3779b185ebca8d;
+
+    for &split in SPLITS {
+        let states = [
+            XXH3State::new(),
+            XXH3State::new_with_seed(seed),
+            XXH3State::new_with_secret(secret).unwrap(),
+            XXH3State::new_with_secret_and_seed(secret, seed).unwrap(),
+        ];
+        let expected = [
+            (xxh3_64bits(&data), xxh3_128bits(&data)),
+            (xxh3_64bits_with_seed(&data, seed), xxh3_128bits_with_seed(&data, seed)),
+            (xxh3_64bits_with_secret(&data, secret).unwrap(), xxh3_128bits_with_secret(&data, secret).unwrap()),
+            (
+                xxh3_64bits_with_secret_and_seed(&data, secret, seed).unwrap(),
+                xxh3_128bits_with_secret_and_seed(&data, secret, seed).unwrap(),
+            ),
+        ];
+        for (mut state, expected) in states.into_iter().zip(expected) {
+            // Feed in two calls so the internal buffer is partly filled
+            state.update(&data[..split / 3]).unwrap();
+            state.update(&data[split / 3..split]).unwrap();
+            let mut resumed = XXH3State::from_checkpoint(&state.to_checkpoint()).unwrap();
+            assert_eq!(resumed.digest_64(), state.digest_64(), "split {}", split);
+            resumed.update(&data[split..]).unwrap();
+            assert_eq!((resumed.digest_64(), resumed.digest_128()), expected, "split {}", split);
+        }
+    }
+}
+
+#[test]
+fn test_checkpoint_layout_is_stable() {
+    let mut state = XXH32State::new(1);
+    state.update(b"abc").unwrap();
+    let checkpoint = state.to_checkpoint();
+
+    assert_eq!(&checkpoint[..4], &CHECKPOINT_MAGIC);
+    assert_eq!(checkpoint[4], CHECKPOINT_VERSION);
+    let mut expected = vec![b'X', b'X', b'H', b'C', 1, 1];
+    expected.extend_from_slice(&1u32.to_le_bytes());
+    expected.extend_from_slice(&3u64.to_le_bytes());
+    expected.push(0);
+    expected.extend_from_slice(&1u32.wrapping_add(0x9E3779B1).wrapping_add(0x85EBCA77).to_le_bytes());
+    expected.extend_from_slice(&1u32.wrapping_add(0x85EBCA77).to_le_bytes());
+    expected.extend_from_slice(&1u32.to_le_bytes());
+    expected.extend_from_slice(&1u32.wrapping_sub(0x9E3779B1).to_le_bytes());
+    expected.push(3);
+    expected.extend_from_slice(b"abc");
+    let checksum = xxh64(&expected);
+    expected.extend_from_slice(&checksum.to_le_bytes());
+    assert_eq!(checkpoint, expected);
+}
+
+#[test]
+fn test_truncated_or_extended_checkpoints_are_rejected() {
+    let data = fill_test_buffer(3_000);
+    let mut xxh32_state = XXH32State::new(0);
+    let mut xxh64_state = XXH64State::new(0);
+    let mut xxh3_state = XXH3State::new_with_seed(5);
+    xxh32_state.update(&data[..1001]).unwrap();
+    xxh64_state.update(&data[..1001]).unwrap();
+    xxh3_state.update(&data[..1001]).unwrap();
+
+    let checkpoints = [xxh32_state.to_checkpoint(), xxh64_state.to_checkpoint(), xxh3_state.to_checkpoint()];
+    for checkpoint in &checkpoints {
+        for len in 0..checkpoint.len() {
+            let truncated = &checkpoint[..len];
+            assert_eq!(XXH32State::from_checkpoint(truncated).err(), Some(XXHashError::InvalidState));
+            assert_eq!(XXH64State::from_checkpoint(truncated).err(), Some(XXHashError::InvalidState));
+            assert_eq!(XXH3State::from_checkpoint(truncated).err(), Some(XXHashError::InvalidState));
+        }
+        let mut extended = checkpoint.clone();
+        extended.push(0);
+        assert_eq!(XXH32State::from_checkpoint(&extended).err(), Some(XXHashError::InvalidState));
+        assert_eq!(XXH64State::from_checkpoint(&extended).err(), Some(XXHashError::InvalidState));
+        assert_eq!(XXH3State::from_checkpoint(&extended).err(), Some(XXHashError::InvalidState));
+    }
+
+    // A checkpoint only restores the algorithm it was taken from
+    assert!(XXH64State::from_checkpoint(&checkpoints[0]).is_err());
+    assert!(XXH3State::from_checkpoint(&checkpoints[1]).is_err());
+    assert!(XXH32State::from_checkpoint(&checkpoints[2]).is_err());
+}
+
+#[test]
+fn test_inconsistent_checkpoints_are_rejected() {
+    let mut state = XXH64State::new(0);
+    state.update(b"0123456789").unwrap();
+    let checkpoint = state.to_checkpoint();
+
+    let mut bad_version = checkpoint.clone();
+    bad_version[4] = CHECKPOINT_VERSION + 1;
+    let bad_version = reseal(bad_version);
+    assert_eq!(XXH64State::from_checkpoint(&bad_version).err(), Some(XXHashError::InvalidState));
+
+    let mut bad_magic = checkpoint.clone();
+    bad_magic[0] = b'Y';
+    let bad_magic = reseal(bad_magic);
+    assert_eq!(XXH64State::from_checkpoint(&bad_magic).err(), Some(XXHashError::InvalidState));
+
+    // Header (6), seed (8), total length (8), then the large_len flag
+    let mut bad_flag = checkpoint.clone();
+    bad_flag[22] = 2;
+    let bad_flag = reseal(bad_flag);
+    assert_eq!(XXH64State::from_checkpoint(&bad_flag).err(), Some(XXHashError::InvalidState));
+
+    // Large-input flag set on a 10-byte input, which takes the merged-lanes path
+    let mut early_large = checkpoint.clone();
+    early_large[22] = 1;
+    let early_large = reseal(early_large);
+    assert_eq!(XXH64State::from_checkpoint(&early_large).err(), Some(XXHashError::InvalidState));
+
+    // Same for XXH32: header (6), seed (4), total length (8), then the flag
+    let mut xxh32_state = XXH32State::new(0);
+    xxh32_state.update(b"0123456789").unwrap();
+    let mut early_large = xxh32_state.to_checkpoint();
+    early_large[18] = 1;
+    let early_large = reseal(early_large);
+    assert_eq!(XXH32State::from_checkpoint(&early_large).err(), Some(XXHashError::InvalidState));
+
+    // Total length no longer agrees with the buffered byte count
+    let mut bad_length = checkpoint.clone();
+    bad_length[14] = 11;
+    let bad_length = reseal(bad_length);
+    assert_eq!(XXH64State::from_checkpoint(&bad_length).err(), Some(XXHashError::InvalidState));
+
+    // XXH3: secret shorter than the minimum, and a short input with a stale buffer count
+    let mut state = XXH3State::new_with_secret(&generate_secret_from_seed(1)).unwrap();
+    state.update(b"abc").unwrap();
+    let checkpoint = state.to_checkpoint();
+    let mut short_secret = checkpoint.clone();
+    short_secret[16..20].copy_from_slice(&100u32.to_le_bytes());
+    let short_secret = reseal(short_secret);
+    assert_eq!(XXH3State::from_checkpoint(&short_secret).err(), Some(XXHashError::InvalidState));
+
+    let mut bad_buffered = checkpoint.clone();
+    let buffered_at = checkpoint.len() - 8 - 256 - 4;
+    bad_buffered[buffered_at] = 4;
+    let bad_buffered = reseal(bad_buffered);
+    assert_eq!(XXH3State::from_checkpoint(&bad_buffered).err(), Some(XXHashError::InvalidState));
+
+    // Seeded, but without the secret derived from the seed: header (6), seed (8), use_seed
+    let mut state = XXH3State::new();
+    state.update(b"abc").unwrap();
+    let mut missing_secret = state.to_checkpoint();
+    missing_secret[6] = 5;
+    missing_secret[14] = 1;
+    let missing_secret = reseal(missing_secret);
+    assert_eq!(XXH3State::from_checkpoint(&missing_secret).err(), Some(XXHashError::InvalidState));
+}
+
+#[test]
+fn test_corrupted_checkpoints_are_rejected() {
+    let data = fill_test_buffer(1_000);
+    let mut xxh32_state = XXH32State::new(0);
+    let mut xxh64_state = XXH64State::new(0);
+    let mut xxh3_state = XXH3State::new_with_seed(5);
+    xxh32_state.update(&data).unwrap();
+    xxh64_state.update(&data).unwrap();
+    xxh3_state.update(&data).unwrap();
+
+    // Every single-bit flip, including in the checksum itself
+    let checkpoints = [xxh32_state.to_checkpoint(), xxh64_state.to_checkpoint(), xxh3_state.to_checkpoint()];
+    for checkpoint in &checkpoints {
+        for bit in 0..checkpoint.len() * 8 {
+            let mut corrupted = checkpoint.clone();
+            corrupted[bit / 8] ^= 1 << (bit % 8);
+            assert_eq!(XXH32State::from_checkpoint(&corrupted).err(), Some(XXHashError::InvalidState), "bit {}", bit);
+            assert_eq!(XXH64State::from_checkpoint(&corrupted).err(), Some(XXHashError::InvalidState), "bit {}", bit);
+            assert_eq!(XXH3State::from_checkpoint(&corrupted).err(), Some(XXHashError::InvalidState), "bit {}", bit);
+        }
+    }
+}
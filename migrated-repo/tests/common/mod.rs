@@ -0,0 +1,15 @@
+//! Helpers shared by the integration tests
+
+/// Fill a buffer the same way as xxHash's sanity test generator
+pub fn fill_test_buffer(len: usize) -> Vec<u8> {
+    const PRIME32: u64 = 2654435761;
+    const PRIME64: u64 = 11400714785074694797;
+
+    let mut byte_gen = PRIME32;
+    let mut buffer = Vec::with_capacity(len);
+    for _ in 0..len {
+        buffer.push((byte_gen >> 56) as u8);
+        byte_gen = byte_gen.wrapping_mul(PRIME64);
+    }
+    buffer
+}
@@ -67,6 +67,19 @@ fn test_xxh3_64_against_reference() {
     assert_eq!(xxh3_64bits_with_seed(b"a", 0x123456789abcdef0), 0xb3d499069b2d173a);
 }
 
+#[test]
+fn test_xxh3_64_mid_length_against_reference() {
+    // 17-128 byte inputs, expected values from C reference
+    let seed64 = 0x123456789abcdef0;
+    let fast = b"xxHash is a very fast hashing algorithm";
+    assert_eq!(xxh3_64bits(fast), 0x82638001991a07ae);
+    assert_eq!(xxh3_64bits_with_seed(fast, seed64), 0x1a110a8b6cbb08f2);
+
+    let longer = b"This is a longer test string to verify the implementation works correctly";
+    assert_eq!(xxh3_64bits(longer), 0xfeba770e8b08973e);
+    assert_eq!(xxh3_64bits_with_seed(longer, seed64), 0x4ff3c1030bc939e2);
+}
+
 #[test]
 fn test_xxh3_128_against_reference() {
     // Expected values from C reference
@@ -79,6 +92,60 @@ fn test_xxh3_128_against_reference() {
     assert_eq!(result_seed.low, 0xb950a1d9e9a4a947);
 }
 
+#[test]
+fn test_xxh3_128_short_against_reference() {
+    // 1-16 byte inputs, expected values from C reference
+    let seed64 = 0x123456789abcdef0;
+    let result = xxh3_128bits(b"abcd");
+    assert_eq!(result.high, 0x8d6b60383dfa90c2);
+    assert_eq!(result.low, 0x1be79eecd1b1353d);
+
+    let result = xxh3_128bits_with_seed(b"abcd", seed64);
+    assert_eq!(result.high, 0x1368893b31983bd3);
+    assert_eq!(result.low, 0xf2799a393f4e153b);
+
+    let result = xxh3_128bits(b"hello world");
+    assert_eq!(result.high, 0xdf8d09e93f874900);
+    assert_eq!(result.low, 0xa99b8775cc15b6c7);
+
+    let result = xxh3_128bits_with_seed(b"hello world", seed64);
+    assert_eq!(result.high, 0xcfcd7cf1de843969);
+    assert_eq!(result.low, 0x15981fbf55d6ff0f);
+}
+
+#[test]
+fn test_xxh3_128_mid_length_against_reference() {
+    // 17-128 byte inputs, expected values from C reference
+    let seed64 = 0x123456789abcdef0;
+    let fast = b"xxHash is a very fast hashing algorithm";
+    let result = xxh3_128bits(fast);
+    assert_eq!(result.high, 0xdad06cc964d75928);
+    assert_eq!(result.low, 0x45356e94fc7f2e8c);
+
+    let result = xxh3_128bits_with_seed(fast, seed64);
+    assert_eq!(result.high, 0x8a91403702a3a5e9);
+    assert_eq!(result.low, 0x3631927ce8ebf22f);
+
+    let longer = b"This is a longer test string to verify the implementation works correctly";
+    let result = xxh3_128bits(longer);
+    assert_eq!(result.high, 0x845b04f72e7dc3ff);
+    assert_eq!(result.low, 0xbfac318b1597a374);
+
+    let result = xxh3_128bits_with_seed(longer, seed64);
+    assert_eq!(result.high, 0xf30a7228b1867a6d);
+    assert_eq!(result.low, 0x0a61176426fb2748);
+}
+
+#[test]
+fn test_xxh3_secret_against_reference() {
+    // The C reference derives its custom secret with XXH3_generateSecret_fromSeed(secret, 0)
+    let secret = generate_secret_from_seed(0);
+    assert_eq!(xxh3_64bits_with_secret(b"hello world", &secret).unwrap(), 0xd447b1ea40e6988b);
+    let result = xxh3_128bits_with_secret(b"hello world", &secret).unwrap();
+    assert_eq!(result.high, 0xdf8d09e93f874900);
+    assert_eq!(result.low, 0xa99b8775cc15b6c7);
+}
+
 #[test]
 fn test_streaming_apis() {
     // Test XXH32 streaming
@@ -97,8 +164,8 @@ fn test_streaming_apis() {
     let mut state3 = XXH3State::new();
     state3.update(b"hello").unwrap();
     state3.update(b" world").unwrap();
-    // Note: streaming for XXH3 has complex implementation, test basic functionality
-    let _result = state3.digest_64();
+    assert_eq!(state3.digest_64(), xxh3_64bits(b"hello world"));
+    assert_eq!(state3.digest_128(), xxh3_128bits(b"hello world"));
 }
 
 #[test]
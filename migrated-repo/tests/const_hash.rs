@@ -0,0 +1,39 @@
+//! `const fn` hashes must match the runtime ones, and evaluate at compile time
+
+use xxhash_migration::*;
+
+mod common;
+use common::fill_test_buffer;
+
+#[test]
+fn test_const_matches_runtime() {
+    let data = fill_test_buffer(4200);
+    let lengths = (0..=512).chain([1023, 1024, 1025, 2048, 4097, 4200]);
+    for len in lengths {
+        let input = &data[..len];
+        for seed in [0, 1, 0x9e3779b185ebca8d] {
+            assert_eq!(xxh32_const(input, seed as u32), xxh32_with_seed(input, seed as u32), "len {}", len);
+            assert_eq!(xxh64_const(input, seed), xxh64_with_seed(input, seed), "len {}", len);
+            assert_eq!(xxh3_64bits_const(input, seed), xxh3_64bits_with_seed(input, seed), "len {}", len);
+        }
+    }
+}
+
+#[test]
+fn test_compile_time_evaluation() {
+    const NAME_ID: u64 = xxh3_64bits_const(b"metrics.requests", 0);
+    const LONG: &[u8; 300] = &[7; 300];
+    const LONG_ID: u64 = xxh3_64bits_const(LONG, 42);
+    const IDS: [u32; 2] = [xxh32_const(b"Ping", 0), xxh32_const(b"Pong", 0)];
+
+    assert_eq!(NAME_ID, xxh3_64bits(b"metrics.requests"));
+    assert_eq!(LONG_ID, xxh3_64bits_with_seed(LONG, 42));
+
+    const PING: u64 = xxh64_const(b"Ping", 0);
+    let kind = match xxh64_with_seed(b"Ping", 0) {
+        PING => "ping",
+        _ => "other",
+    };
+    assert_eq!(kind, "ping");
+    assert_ne!(IDS[0], IDS[1]);
+}
@@ -0,0 +1,77 @@
+//! Integer-key functions must equal hashing the key's little-endian bytes
+
+use xxhash_migration::*;
+
+/// SplitMix64, so the keys and seeds are reproducible without a dependency
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// Edge keys, then random ones
+fn keys(rng: &mut SplitMix64) -> Vec<u128> {
+    let mut keys = vec![0, 1, u32::MAX as u128, u64::MAX as u128, 1 << 64, u128::MAX];
+    keys.extend((0..10_000).map(|_| (rng.next() as u128) << 64 | rng.next() as u128));
+    keys
+}
+
+#[test]
+fn test_xxh32_u32() {
+    let mut rng = SplitMix64(1);
+    for key in keys(&mut rng) {
+        let key = key as u32;
+        let seed = rng.next() as u32;
+        assert_eq!(xxh32_u32(key), xxh32(&key.to_le_bytes()), "key {:#x}", key);
+        assert_eq!(xxh32_u32_with_seed(key, seed), xxh32_with_seed(&key.to_le_bytes(), seed), "key {:#x}, seed {:#x}", key, seed);
+    }
+}
+
+#[test]
+fn test_xxh64_u64() {
+    let mut rng = SplitMix64(2);
+    for key in keys(&mut rng) {
+        let key = key as u64;
+        let seed = rng.next();
+        assert_eq!(xxh64_u64(key), xxh64(&key.to_le_bytes()), "key {:#x}", key);
+        assert_eq!(xxh64_u64_with_seed(key, seed), xxh64_with_seed(&key.to_le_bytes(), seed), "key {:#x}, seed {:#x}", key, seed);
+    }
+}
+
+#[test]
+fn test_xxh3_64_u64() {
+    let mut rng = SplitMix64(3);
+    for key in keys(&mut rng) {
+        let key = key as u64;
+        let seed = rng.next();
+        assert_eq!(xxh3_64_u64(key), xxh3_64bits(&key.to_le_bytes()), "key {:#x}", key);
+        assert_eq!(xxh3_64_u64_with_seed(key, seed), xxh3_64bits_with_seed(&key.to_le_bytes(), seed), "key {:#x}, seed {:#x}", key, seed);
+    }
+}
+
+#[test]
+fn test_xxh3_64_u128() {
+    let mut rng = SplitMix64(4);
+    for key in keys(&mut rng) {
+        let seed = rng.next();
+        assert_eq!(xxh3_64_u128(key), xxh3_64bits(&key.to_le_bytes()), "key {:#x}", key);
+        assert_eq!(xxh3_64_u128_with_seed(key, seed), xxh3_64bits_with_seed(&key.to_le_bytes(), seed), "key {:#x}, seed {:#x}", key, seed);
+    }
+}
+
+#[test]
+fn test_small_seeds() {
+    // Seed 0 takes a separate branch in the seeded slice functions
+    for seed in 0..4 {
+        assert_eq!(xxh3_64_u64_with_seed(42, seed), xxh3_64bits_with_seed(&42u64.to_le_bytes(), seed));
+        assert_eq!(xxh3_64_u128_with_seed(42, seed), xxh3_64bits_with_seed(&42u128.to_le_bytes(), seed));
+        assert_eq!(xxh64_u64_with_seed(42, seed), xxh64_with_seed(&42u64.to_le_bytes(), seed));
+        assert_eq!(xxh32_u32_with_seed(42, seed as u32), xxh32_with_seed(&42u32.to_le_bytes(), seed as u32));
+    }
+}
@@ -0,0 +1,86 @@
+//! Every XXH3 backend supported by the host must match the scalar one bit for bit
+//!
+//! The backend override is process-wide, so the comparisons run one at a
+//! time under a lock.
+
+use std::sync::Mutex;
+
+use xxhash_migration::constants::{XXH3_SECRET_INLINE_MAX, XXH3_SECRET_SIZE_MIN};
+use xxhash_migration::*;
+
+mod common;
+use common::fill_test_buffer;
+
+static BACKEND_LOCK: Mutex<()> = Mutex::new(());
+
+/// Lengths covering every long-input path: a partial first block, exact
+/// block multiples for the default secret (1024 bytes) and odd tails
+const LENGTHS: &[usize] = &[241, 255, 256, 257, 511, 1023, 1024, 1025, 2048, 4096, 5000, 16384, 65537, 100_003];
+
+/// All outputs of one configuration, for one backend
+fn hash_all(data: &[u8], secret: &[u8], seed: u64) -> Vec<u128> {
+    let mut out = Vec::new();
+    for &len in LENGTHS {
+        let input = &data[..len];
+        out.push(xxh3_64bits(input) as u128);
+        out.push(xxh3_64bits_with_seed(input, seed) as u128);
+        out.push(xxh3_64bits_with_secret(input, secret).unwrap() as u128);
+        out.push(u128::from(xxh3_128bits(input)));
+        out.push(u128::from(xxh3_128bits_with_seed(input, seed)));
+        out.push(u128::from(xxh3_128bits_with_secret_and_seed(input, secret, seed).unwrap()));
+
+        // Streaming, in chunks that straddle the internal buffer
+        let mut state = XXH3State::new_with_secret(secret).unwrap();
+        for chunk in input.chunks(97) {
+            state.update(chunk).unwrap();
+        }
+        out.push(state.digest_64() as u128);
+        out.push(u128::from(state.digest_128()));
+    }
+    out
+}
+
+fn supported_backends() -> Vec<XXH3Backend> {
+    XXH3Backend::ALL.into_iter().filter(|backend| backend.is_supported()).collect()
+}
+
+#[test]
+fn test_backends_are_bit_identical() {
+    let _guard = BACKEND_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let buffer = fill_test_buffer(100_003 + 512);
+    let data = &buffer[..100_003];
+
+    // Secret sizes change the block length and the scramble position; streaming
+    // states only take secrets over XXH3_SECRET_INLINE_MAX bytes with alloc
+    let largest = if cfg!(feature = "alloc") { 512 } else { XXH3_SECRET_INLINE_MAX };
+    for secret_size in [XXH3_SECRET_SIZE_MIN, 137, 192, 200, 255, largest] {
+        let secret = &buffer[100_003..100_003 + secret_size];
+        for seed in [0, 0x9e3779b185ebca8d] {
+            force_xxh3_backend(Some(XXH3Backend::Scalar)).unwrap();
+            let expected = hash_all(data, secret, seed);
+            for backend in supported_backends() {
+                force_xxh3_backend(Some(backend)).unwrap();
+                assert_eq!(hash_all(data, secret, seed), expected, "{:?}, secret {}, seed {:#x}", backend, secret_size, seed);
+            }
+        }
+    }
+    force_xxh3_backend(None).unwrap();
+}
+
+#[test]
+fn test_backend_override() {
+    let _guard = BACKEND_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    assert!(XXH3Backend::Scalar.is_supported());
+    assert!(XXH3Backend::detect().is_supported());
+
+    force_xxh3_backend(Some(XXH3Backend::Scalar)).unwrap();
+    assert_eq!(XXH3Backend::active(), XXH3Backend::Scalar);
+
+    for backend in XXH3Backend::ALL {
+        let expected = if backend.is_supported() { Ok(()) } else { Err(XXHashError::BackendUnavailable(backend)) };
+        assert_eq!(force_xxh3_backend(Some(backend)), expected, "{:?}", backend);
+    }
+
+    force_xxh3_backend(None).unwrap();
+    assert_eq!(XXH3Backend::active(), XXH3Backend::detect());
+}
@@ -0,0 +1,567 @@
+//! Known-answer vectors for XXH3, generated with the C reference implementation.
+//!
+//! Inputs come from the same pseudo-random buffer used by xxHash's own
+//! sanity checks, so every vector can be reproduced with `xxhsum`'s tooling.
+
+use xxhash_migration::constants::{XXH3_SECRET_INLINE_MAX, XXH3_SECRET_SIZE_MIN};
+use xxhash_migration::*;
+
+mod common;
+use common::fill_test_buffer;
+
+/// Seed used for the seeded column of every vector table
+const TEST_SEED: u64 = 0x9e3779b185ebca8d;
+
+/// Custom secret of an arbitrary size, taken from the test buffer at offset 7
+fn test_secret(size: usize) -> Vec<u8> {
+    fill_test_buffer(size + 7)[7..].to_vec()
+}
+
+/// (length, XXH3_64 unseeded, XXH3_64 with TEST_SEED)
+const XXH3_64_17TO128: &[(usize, u64, u64)] = &[
+    (17, 0x796f5acd3a60f862, 0xf3ec5067f4306db3),
+    (31, 0x5d516692ca764c50, 0x9b37274259c549c6),
+    (32, 0x9feaddbdbf57eed3, 0x2199fab1534893d9),
+    (33, 0xabfb2d081b400a10, 0xad56348da574bb6d),
+    (48, 0x397da259ecba1f11, 0xadc2cbaa44acc616),
+    (63, 0x83d74a75f2c2577a, 0xc3ab9c8b53960dc7),
+    (64, 0x9cb48487720ec49d, 0x4fe8895db9b8c077),
+    (65, 0xfd81aac4bebc3883, 0xad80aeec1fc9e0a7),
+    (80, 0xbcdefbbb2c47c90a, 0xc6dd0cb699532e73),
+    (95, 0xc0dd460b48116cda, 0xc81d7c3a751d3b94),
+    (96, 0x935a769a7f94776f, 0x70cf51937e500540),
+    (97, 0xca4ca268fd3c3a6c, 0xee461d3add7ee6c9),
+    (112, 0xd13d8f57931eef19, 0xa276b2e306e77fe5),
+    (127, 0x2408ed71323d6096, 0x41d2f0c3f483208f),
+    (128, 0xfcff24126754d861, 0x73fde75280646649),
+];
+
+#[test]
+fn test_xxh3_64_17to128_vectors() {
+    for &(len, expected, expected_seeded) in XXH3_64_17TO128 {
+        let data = fill_test_buffer(len);
+        assert_eq!(xxh3_64bits(&data), expected, "len {}", len);
+        assert_eq!(xxh3_64bits_with_seed(&data, TEST_SEED), expected_seeded, "len {}", len);
+    }
+}
+
+/// (length, XXH3_64 unseeded, XXH3_64 with TEST_SEED)
+const XXH3_64_129TO240: &[(usize, u64, u64)] = &[
+    (129, 0x98f1b0a679a2ca29, 0x21fffdbca099c844),
+    (130, 0x7f6c531cf53eeb30, 0xf1450ca53f2203e1),
+    (131, 0x9b4fa7d0b15e0a57, 0x9f5752ac8ef77aac),
+    (132, 0x3a38439a30b3c4cc, 0xb27d306c98996fe6),
+    (133, 0xa0699d046c5c5362, 0x763dd6652b6c34ed),
+    (134, 0x84a906ec5fa118a0, 0x46ff4d8e7e37ca25),
+    (135, 0x6758fdc6d9906706, 0x92fdfbdac216eb79),
+    (136, 0x663df5809a9645ee, 0xb8891527e8c1fbc2),
+    (137, 0x03e54245d16b0b4c, 0xd0e671a9f60ecba7),
+    (138, 0xea3fe83e2ff48610, 0x939cd4fe1c054f3a),
+    (139, 0x7d93965f3e0e3dfd, 0x8d78d1fd9d0a8ee3),
+    (140, 0xca0d611f4c6d0492, 0x0ee6e507ed38899c),
+    (141, 0xfcfc9295b140e881, 0xd8a88f327d66cc9b),
+    (142, 0x7564a4e9782e2b1a, 0x0bf86ca5cb4a3000),
+    (143, 0x7a7813693f1f4edb, 0x43b3274ae68d5742),
+    (144, 0xc440d47e5f03fe58, 0x858d16b71f4dd859),
+    (145, 0x6b0a35a6cb2da39d, 0x82aa49a77e3dc26f),
+    (146, 0xcd519d5d390192f2, 0x42e2e13ab27db0a0),
+    (147, 0xf3b25ac47bd6e81e, 0x0fc6aac279201900),
+    (148, 0xa57c549bca723453, 0x68161b922ed9a946),
+    (149, 0x466973ffc71b4b5d, 0x4c3a019e1cfb23aa),
+    (150, 0xf37b3081c8df11f6, 0x608b69ef63f8c716),
+    (151, 0x909835aea62bd752, 0x7735a32d2ef001dc),
+    (152, 0xe11b4a1098ad4b8b, 0x6291a0d978d5c565),
+    (153, 0x98bf35a509dbaeab, 0x1804f3b0aeb1f981),
+    (154, 0x70bbf262d1b53f51, 0xfcea63d51474cdf0),
+    (155, 0xb6d7c54afff3684b, 0x6783d00565d9d687),
+    (156, 0x9dcff5b3b7447702, 0xed931d3f44416ccd),
+    (157, 0x2a959079a3f984b7, 0x6272761ed82135de),
+    (158, 0x9f93b18d6c70e091, 0x67532b69eea9f165),
+    (159, 0x7cb32a6ecdebaf1c, 0xacff1f7b6cc1fdc9),
+    (160, 0x9d03a319ed4cbd2b, 0x3825c75ffe70fde0),
+    (161, 0x7f8a89c6be63f006, 0xee122d625550adb5),
+    (162, 0x2474a16d1e7eb282, 0x9bb5f1fdf209f6e6),
+    (163, 0x8db1e66ec827c3d9, 0x39151d86b74d6357),
+    (164, 0x32dcb8a53dc0c161, 0x699179710733b633),
+    (165, 0x3ec4094d7bb7628d, 0xfb33609f8b3be2c2),
+    (166, 0xb6a472574d1f8503, 0x77b4550d54bd15b4),
+    (167, 0xa56a74f7014b726a, 0x13fef783ca42491b),
+    (168, 0x49da18ca1c8530b6, 0x609f4fa16dbff2f0),
+    (169, 0xbe73d1d17dba9d7b, 0x984614799adf185d),
+    (170, 0x81d2f5665a40be4c, 0xd673758e56f598e1),
+    (171, 0x3720366743bf39b1, 0xc4ac6c4773e09c8b),
+    (172, 0x59828cd9838e43e8, 0xbcbad7aa03d9ca97),
+    (173, 0x423fcb5a1fe143ed, 0x78d025f167a41d1b),
+    (174, 0xd29deb0ecd352d5c, 0x74ede1bcdb8412dc),
+    (175, 0xa414b77df2fdabae, 0x8f19b0614db0b814),
+    (176, 0x6a79c99804538253, 0x25b3750065ba47ef),
+    (177, 0x6fc0e6e420b6629b, 0xb1d1d87eee7672df),
+    (178, 0x46331b1df91c423f, 0xd735a55a58764ca4),
+    (179, 0x2f07114318b350bc, 0x1d5be67985c1a686),
+    (180, 0xc801ef90ce0e5482, 0x46e2c5cda8c31d49),
+    (181, 0x0fb3d9f3fb6159c2, 0x653f3124125d03a8),
+    (182, 0xc4c2a22a0a17ae1d, 0xff709c491d108570),
+    (183, 0xed4887e95b6ddeea, 0x248ed7f1f3fd278b),
+    (184, 0x26acc2aabebe7708, 0xe6385cf95a3b2527),
+    (185, 0x04499ad14a01cdfa, 0x7c602a71149bcf8d),
+    (186, 0x9c76ca622dfd0557, 0x0f2444a4eada96a4),
+    (187, 0x41a93ee0800caaf9, 0x90a15b89f499f00d),
+    (188, 0x511144b10fefda3a, 0xdb7ea67ab226cd9a),
+    (189, 0xee03bb33da3ed624, 0xc6fc08280035a2c7),
+    (190, 0x24ed6f75412d1e43, 0x47bec6016cc3e54a),
+    (191, 0x759ffc8ff8fb94bd, 0xabcd155ad6e9f3b6),
+    (192, 0xaf9f58e78b8d3587, 0x69e006aa2156c999),
+    (193, 0x32fab2adcae0d00d, 0x52d31c60adc3d7b3),
+    (194, 0x49eb64c2e5d65fa7, 0xc2feda6d9221beaf),
+    (195, 0xcd94217ee362ec3a, 0xba68003d370cb3d9),
+    (196, 0xf5783f1a3c6f6e11, 0x7868bdd61af9be3d),
+    (197, 0x894bbc1dd7e5674f, 0x652c9ab1c8d39d5e),
+    (198, 0xe2689c6cb4d31aaa, 0x6dd1d6df5511faa7),
+    (199, 0x673ad58bfd993705, 0xcca3f1cd19a25150),
+    (200, 0xbddca58935d7c038, 0x5b899e984b88db8d),
+    (201, 0x1a37e8aea84c276f, 0xdf67e6d40736eed4),
+    (202, 0x7133a9b6daa64c4f, 0xb4877cef4d15a60c),
+    (203, 0xb8a63260a11f1047, 0xa9debf6a44d6c343),
+    (204, 0xf81ed05ed82a5f64, 0x00a56571768fc3b1),
+    (205, 0x9aaf89a02b341b4d, 0xb8cfb9b3c7272312),
+    (206, 0xe5e6d6fca7ee38b9, 0x3957a991e22d881b),
+    (207, 0x56ffe054abc6f401, 0x056f05b3d4f7bcef),
+    (208, 0x2cf1e5eb7433aa4d, 0xa351c8376638c444),
+    (209, 0x55d4b044757b2153, 0x87d10de06020d17d),
+    (210, 0x1f69d7786b302b2c, 0x6132710e50e40a5c),
+    (211, 0x3e33c814b1d6a382, 0xcb64e9ea84cb28c5),
+    (212, 0x7e49add5e90db2b5, 0xde9b97df13d80b9d),
+    (213, 0xa829342475f34565, 0x8ee174c9b237f8af),
+    (214, 0x5ff1756998e2de58, 0x13c52f2a2e1d5992),
+    (215, 0x2dc9ec2dfa977d2d, 0x4defcfd87deb6deb),
+    (216, 0x80c06422da6b2014, 0x4de3fac5a41bf995),
+    (217, 0xae20a79cab85bdd9, 0x9f30a3800e1e8a2a),
+    (218, 0x3c9dba381565379d, 0xd7ea324a7dcba190),
+    (219, 0xb14f87797a8d4e11, 0xb048e218eb663459),
+    (220, 0x85906cc9b50aad58, 0x9f92416b7a9be313),
+    (221, 0xe1879d3fb79d634c, 0xcbddc9d69e2c6628),
+    (222, 0xb9163b558664d356, 0xcd627e7ca214ebfd),
+    (223, 0x6e495316b983036e, 0x9f9cefaccfee917d),
+    (224, 0x3f3250c7e92c871a, 0x17023a9a6f1156db),
+    (225, 0x1e93dc9165079888, 0xcc6043cf5eb743ec),
+    (226, 0x1079cba535d0645f, 0x103b79310dd8559d),
+    (227, 0x6512697fc21cbf8c, 0xf5843a5e1cf14646),
+    (228, 0x08f56302c634edc6, 0x9efb771d3b4c7af9),
+    (229, 0x0fa5055ab340134d, 0xd9418c945bd23b5d),
+    (230, 0x358940707397c206, 0x7d15402dda9ba222),
+    (231, 0x95cac1aa565ca0ee, 0x1bd48a08abd40673),
+    (232, 0xc087038879a68c72, 0x6bc086b684ecc59b),
+    (233, 0xcc5dfd5f26f2007b, 0xdaa0803a50af57bc),
+    (234, 0xdf618b5211de4c44, 0x6ae5a200deb230f6),
+    (235, 0x75f3ff407516816b, 0xcaacd909a07b2cde),
+    (236, 0xd551d984066ea00a, 0xe265da4b7010f56c),
+    (237, 0x86f6ec327e7fc887, 0x50d19364e2ed470a),
+    (238, 0x267b67b37098ff8e, 0x43c23d8a13d3212a),
+    (239, 0x16ce2b9d3b28805d, 0xf59f5c23fcebd3b7),
+    (240, 0x81c3c2b67f568ccf, 0xcc0f58c27ef3d8ee),
+];
+
+/// (length, XXH3_64 with a 147-byte custom secret)
+const XXH3_64_129TO240_SECRET: &[(usize, u64)] = &[
+    (129, 0x49e96035a8476dcf),
+    (160, 0x639d6c91fe54cfba),
+    (192, 0xd3b52d98311f0099),
+    (223, 0xce2e2474fad77edc),
+    (240, 0xdd1f0faf1a8164c0),
+];
+
+#[test]
+fn test_xxh3_64_129to240_vectors() {
+    for &(len, expected, expected_seeded) in XXH3_64_129TO240 {
+        let data = fill_test_buffer(len);
+        assert_eq!(xxh3_64bits(&data), expected, "len {}", len);
+        assert_eq!(xxh3_64bits_with_seed(&data, TEST_SEED), expected_seeded, "len {}", len);
+    }
+}
+
+#[test]
+fn test_xxh3_64_129to240_custom_secret() {
+    let secret = test_secret(XXH3_SECRET_SIZE_MIN + 11);
+    for &(len, expected) in XXH3_64_129TO240_SECRET {
+        let data = fill_test_buffer(len);
+        assert_eq!(xxh3_64bits_with_secret(&data, &secret).unwrap(), expected, "len {}", len);
+    }
+}
+
+/// (length, XXH3_64 unseeded, XXH3_64 with TEST_SEED, XXH3_64 with a 147-byte custom secret)
+const XXH3_64_LONG: &[(usize, u64, u64, u64)] = &[
+    (241, 0xc5a639ecd2030e5e, 0xdda9b0a161d4829a, 0x454805371df98a91),
+    (255, 0xe98f979f4ed8a197, 0x2aca7901d9538c75, 0xe1e3461712968b3e),
+    (256, 0x55de574ad89d0ac5, 0x4d30234b7a3aa61c, 0xd4cba59e2e2cf9f0),
+    (257, 0xb17fd5a8ae75bb0b, 0x802a6fbf3cacd97c, 0x1e4b71e703d08492),
+    (511, 0x8089715b163e7fc0, 0x90ec0377ba8d6002, 0x13e7046bc1c1f16a),
+    (512, 0x617e49599013cb6b, 0x3ce457de14c27708, 0x7564693dd526e28d),
+    (1023, 0x87a8f7b2f2e22496, 0x0f0f02de8590e1b5, 0x6df5a1773b876cfb),
+    (1024, 0xdd85c9b5c1109c5c, 0xef368a8a2ebabaef, 0x3538a2d1ea7410d0),
+    (1025, 0xd870c0fa13211c6a, 0x96792bcf9af88519, 0xe33739f32d405604),
+    (1088, 0xcc1450ea6b52a8f4, 0x542632d046f3dde4, 0x8bcaeddf41f82a85),
+    (2048, 0xdd59e2c3a5f038e0, 0x66f81670669ababc, 0xd32e975821d6519f),
+    (2367, 0xcb37aeb9e5d361ed, 0xd2db3415b942b42a, 0x293fa8e5173bb5e7),
+    (4096, 0xe91206429d1f48f9, 0x2a3bbb20a5439dcd, 0x80c043073074a679),
+    (10000, 0xbcd883507019ca90, 0xcb4fc4745fe1706b, 0x0e747a407b4f9dbe),
+];
+
+#[test]
+fn test_xxh3_64_long_vectors() {
+    let secret = test_secret(XXH3_SECRET_SIZE_MIN + 11);
+    for &(len, expected, expected_seeded, expected_secret) in XXH3_64_LONG {
+        let data = fill_test_buffer(len);
+        assert_eq!(xxh3_64bits(&data), expected, "len {}", len);
+        assert_eq!(xxh3_64bits_with_seed(&data, TEST_SEED), expected_seeded, "len {}", len);
+        assert_eq!(xxh3_64bits_with_secret(&data, &secret).unwrap(), expected_secret, "len {}", len);
+    }
+}
+
+/// (length, XXH3_128 unseeded, XXH3_128 with TEST_SEED)
+const XXH3_128_0TO16: &[(usize, u128, u128)] = &[
+    (0, 0x99aa06d3014798d86001c324468d497f, 0x00feaa732a3ce25ea986dfc5d7605bfe),
+    (1, 0xa6cd5e9392000f6ac44bdff4074eecdb, 0x20e49abcc53b3842032be332dd766ef8),
+    (2, 0x76750c3c7bf956687a9978044cb8a8bb, 0x7b96e6a600dae67d764b35c90519ad88),
+    (3, 0x20efc49ff02422ea54247382a8d6b94d, 0x1c7ecf6a308cf00e634b8990b4976373),
+    (4, 0x970d585ac632bf8e2e7d8d6876a39fe9, 0x3d53e5dfd837d927bfaf51f1e67e0b0f),
+    (5, 0x62ed587687606b4e057c7ed2c01fa1d1, 0xfac738e8fec3771567a0c170d32090d7),
+    (6, 0x082afe0b8162d12a3e7039bdda43cfc6, 0x014bd95a51ca5ddbc5b54d56038e4e40),
+    (7, 0xdd9b6039f79ec416081c22dd284a2f0a, 0x833cba0282dd6619d5da197923938e53),
+    (8, 0x47a7f080d82bb45664c69cab4bb21dc5, 0xf50cec145bcd5c5a7b29471dc729b5ff),
+    (9, 0x564ef6078950d457ed7ccbc501eb7501, 0x6b380b43ffa61042aef5dfc0ac9f9044),
+    (10, 0xc6f795961d7a19557a16d0a993b929b1, 0x977507c07d31fb34fc69c7d7394cc927),
+    (11, 0x566645f72f87105958378517e765ae7c, 0x6cbbd3b333f3d9fd70a352d765d412df),
+    (12, 0x6e3efd8fc7802b18061a192713f69ad9, 0xff0d60acd02ed4015d92b5d7190b12d1),
+    (13, 0x30d4b04dda0e25144cb46e6932d0bce2, 0x70f1e535cae27abcab0af7c25373b4fd),
+    (14, 0xb2623398aa0bda1e352b80797cda6247, 0xd335dbfeb5cf45210194c9941b36fdaa),
+    (15, 0xc402609e57ee5772958955df1889e6bc, 0x48864ef580a95f6b6a0a9ca5fd33cb9d),
+    (16, 0xc68c368ecf8a9c05562980258a998629, 0x6ffcb80cd33085c80346d13a7a5498c7),
+];
+
+#[test]
+fn test_xxh3_128_0to16_vectors() {
+    for &(len, expected, expected_seeded) in XXH3_128_0TO16 {
+        let data = fill_test_buffer(len);
+        assert_eq!(u128::from(xxh3_128bits(&data)), expected, "len {}", len);
+        assert_eq!(u128::from(xxh3_128bits_with_seed(&data, TEST_SEED)), expected_seeded, "len {}", len);
+    }
+}
+
+/// (length, XXH3_128 unseeded, XXH3_128 with TEST_SEED, XXH3_128 with a 147-byte custom secret)
+const XXH3_128_17TO240: &[(usize, u128, u128, u128)] = &[
+    (17, 0x955fa78643ed3669abbc12d11973d7db, 0xd77681219e464828980a14119985a7df, 0x604cc5ee8f142950e94eb4616009b975),
+    (31, 0x301048a7ab476d21ec8365e74dc00653, 0x4639cf7b77ba9096d74750f8952360c3, 0xb40bddca373a4f2b0826f7a00a7d7826),
+    (32, 0x98fc6458710dc2e8278410a17595e3f9, 0xcc587e4fcdb86bc50054e82631cef166, 0x125fb63cc4369aec28feafa7caf376dc),
+    (33, 0x3103c192ceaa2dede593bc4e5914c9d1, 0x21273c8190c645cdc361d36cea597c31, 0xb2407e682b957af2f7402a52b40db03d),
+    (64, 0x6d90e81a9b0fd622efdb6a44690721a9, 0x37b738968d40bda59405ba2affa95ceb, 0x40ebda012db18e865638769ed33e3e38),
+    (65, 0x6c074d65e54db85afe2f650fa500ec6e, 0x72503a6fa8d07adb9d60c345e5c297cd, 0x1f99b7e7674a4b4aebe6ff11bceda8e5),
+    (96, 0xd9d0b885f56c93f1e9324473ea9afebe, 0x6f9ed3c2008cb388d61f3ab58705c405, 0x60166d062d8c659423f2a73c006f4795),
+    (97, 0x09dff37faa6b284c7c87228ae9671ba7, 0x14e68f850b481ada49ea87f2afe44f66, 0x5dea30ea8fcdb3553988b2ee2211c48a),
+    (128, 0x39992220e045260aebb15e34a7fb5ab1, 0xa0f7ccb68ee02add8394f5c51f1d8246, 0x1df8cce15fe35b2cb8feec0b6b6eaf60),
+    (129, 0x03815fc91f1b30b686c9e3bc8f0a3b5c, 0xad559266067c0bf3d4aae26fcec7dc03, 0x72d4d4395002b1509def70d87b89ed7b),
+    (130, 0x90e701a84d20072fbe0500e135cd4b35, 0x2441e0a784d4545ac2822b8e003156ee, 0x1c55429ed8bbdec1ddbea0a707c25741),
+    (159, 0x37562ea4ac14c650996ed46536cc0ef5, 0xc6f19f9e4243b2519bb496bb4447b646, 0xf628df357d4d234f366637379715d5c5),
+    (160, 0xba5d218964b622ad737126c8d7c09cee, 0xc6b7abc26def52ac46a4a3f67ccd556e, 0xa1564fec41891dcf250196b1551d54a1),
+    (161, 0x58621e54439ee75c524bf4f00d882c4e, 0x940856fbf1ab951d969723dc5eb12aeb, 0xb3c5644903aa4e301c3ea0bf48135821),
+    (192, 0x064934db40706c3d0679e7f625e389d9, 0xc1e549baf8d0d863fd5412027e573a96, 0xd727dc9f932a3654296fff5080ac60fe),
+    (223, 0x3f249a473e72280199a2091fbec06cfa, 0xd16ea4253f84294f3349ffb248ef0f0f, 0x3cb6607a24efda77075dd2c669124bb9),
+    (224, 0xf20d710ddbfbcab18b5b0074a7eb57da, 0x04ac8278bf6bb7bc70835854be62cb58, 0xe82557a8e700a94245c17e9731c621aa),
+    (239, 0xe59fc6554b5008bcf895e8b860b8a593, 0xd6701eb51fc21716c0a8b4c9698db33d, 0xc73c1d7278fa152f24f6932c00efbf5f),
+    (240, 0xaa4202daa2769dc85c9aae94c8ebe5a0, 0x29d2133d6ea58c5b604e98db085c1864, 0x8033fd83d4336ca929dd17317e40cba2),
+];
+
+#[test]
+fn test_xxh3_128_17to240_vectors() {
+    let secret = test_secret(XXH3_SECRET_SIZE_MIN + 11);
+    for &(len, expected, expected_seeded, expected_secret) in XXH3_128_17TO240 {
+        let data = fill_test_buffer(len);
+        assert_eq!(u128::from(xxh3_128bits(&data)), expected, "len {}", len);
+        assert_eq!(u128::from(xxh3_128bits_with_seed(&data, TEST_SEED)), expected_seeded, "len {}", len);
+        assert_eq!(u128::from(xxh3_128bits_with_secret(&data, &secret).unwrap()), expected_secret, "len {}", len);
+    }
+}
+
+/// (length, XXH3_128 unseeded, XXH3_128 with TEST_SEED)
+const XXH3_128_LONG: &[(usize, u128, u128)] = &[
+    (241, 0x99a80ecf0ecfc647c5a639ecd2030e5e, 0xec64afae6a137582dda9b0a161d4829a),
+    (256, 0x8b1c66091423d28855de574ad89d0ac5, 0xaaa57235b92d5e7c4d30234b7a3aa61c),
+    (511, 0x9f7619cb8d250f0d8089715b163e7fc0, 0xb52cae55536e9fb990ec0377ba8d6002),
+    (1023, 0xe8083e4d83214c3c87a8f7b2f2e22496, 0x96b80fe329ce5e350f0f02de8590e1b5),
+    (1024, 0x0d30d24071c64c57dd85c9b5c1109c5c, 0x17600efe2b493a18ef368a8a2ebabaef),
+    (1025, 0xfd3ee4fe7f2954c6d870c0fa13211c6a, 0x2c383949f57bf7e196792bcf9af88519),
+    (1088, 0xe0fa3b9e9fb69d83cc1450ea6b52a8f4, 0xc0dd009b78c40121542632d046f3dde4),
+    (2048, 0xf736557fd47073a5dd59e2c3a5f038e0, 0x23cc3a2e75ebaaea66f81670669ababc),
+    (2049, 0x4cd2bd192f2d70bdd3afa4329779b921, 0xe4000f7a288a82cee48083836cd58024),
+    (4096, 0xb9cfaea2ca5626a4e91206429d1f48f9, 0x8fbc8fd4d526d1bd2a3bbb20a5439dcd),
+    (10000, 0xe20727cefc44ead3bcd883507019ca90, 0x1029c26e83437399cb4fc4745fe1706b),
+];
+
+/// (secret size, length, XXH3_128 with that custom secret), around block boundaries
+const XXH3_128_LONG_SECRET: &[(usize, usize, u128)] = &[
+    (147, 640, 0xce1ed41638bea10a46679b44db4dc036),
+    (147, 641, 0x0e346f74e20d7f95c0f26241c55db4e6),
+    (147, 1280, 0x69d503954a4ca67f435f9eb1675ea225),
+    (147, 1281, 0xdaa57f92107a2ca5dc2db93c9ae6807f),
+    (147, 1919, 0xece8723179a75c7874d2536c2c5d7259),
+    (256, 1536, 0x41e9eab14b367d5f0c8788d33c885708),
+    (256, 1537, 0xf23793128f8dacf3e84212a1fe856809),
+    (256, 3072, 0xf229d044a6da2cc12b8d91290aa674ee),
+    (256, 3073, 0xa7e3c5efdf48e37fe53739096704bf21),
+    (256, 4607, 0x36aed8f9fd2eaaede854b1c70e4a107f),
+];
+
+#[test]
+fn test_xxh3_128_long_vectors() {
+    for &(len, expected, expected_seeded) in XXH3_128_LONG {
+        let data = fill_test_buffer(len);
+        assert_eq!(u128::from(xxh3_128bits(&data)), expected, "len {}", len);
+        assert_eq!(u128::from(xxh3_128bits_with_seed(&data, TEST_SEED)), expected_seeded, "len {}", len);
+    }
+}
+
+#[test]
+fn test_xxh3_128_long_block_boundaries() {
+    for &(secret_size, len, expected) in XXH3_128_LONG_SECRET {
+        let secret = test_secret(secret_size);
+        let data = fill_test_buffer(len);
+        let actual = xxh3_128bits_with_secret(&data, &secret).unwrap();
+        assert_eq!(u128::from(actual), expected, "secret {} len {}", secret_size, len);
+    }
+}
+
+/// Feed `data` to a state in chunks of `chunk` bytes
+fn update_in_chunks(state: &mut XXH3State, data: &[u8], chunk: usize) {
+    for piece in data.chunks(chunk) {
+        state.update(piece).unwrap();
+    }
+}
+
+#[test]
+fn test_xxh3_streaming_matches_oneshot() {
+    let data = fill_test_buffer(4500);
+    let secret = test_secret(XXH3_SECRET_SIZE_MIN + 11);
+    let lengths = [0, 1, 16, 17, 128, 129, 240, 241, 255, 256, 257, 511, 512, 513, 1023, 1024, 1025, 2048, 4500];
+    let chunks = [1, 7, 63, 64, 65, 255, 256, 257, 1000, 5000];
+
+    for &len in &lengths {
+        let input = &data[..len];
+        for &chunk in &chunks {
+            let mut state = XXH3State::new();
+            update_in_chunks(&mut state, input, chunk);
+            assert_eq!(state.digest_64(), xxh3_64bits(input), "len {} chunk {}", len, chunk);
+            assert_eq!(state.digest_128(), xxh3_128bits(input), "len {} chunk {}", len, chunk);
+
+            let mut state = XXH3State::new_with_seed(TEST_SEED);
+            update_in_chunks(&mut state, input, chunk);
+            assert_eq!(state.digest_64(), xxh3_64bits_with_seed(input, TEST_SEED), "len {} chunk {}", len, chunk);
+            assert_eq!(state.digest_128(), xxh3_128bits_with_seed(input, TEST_SEED), "len {} chunk {}", len, chunk);
+
+            let mut state = XXH3State::new_with_secret(&secret).unwrap();
+            update_in_chunks(&mut state, input, chunk);
+            assert_eq!(state.digest_64(), xxh3_64bits_with_secret(input, &secret).unwrap(), "len {} chunk {}", len, chunk);
+            assert_eq!(state.digest_128(), xxh3_128bits_with_secret(input, &secret).unwrap(), "len {} chunk {}", len, chunk);
+        }
+    }
+}
+
+#[test]
+fn test_xxh3_streaming_large_input() {
+    // Several blocks worth of data, fed in uneven pieces
+    let data = fill_test_buffer(1 << 20);
+    let mut state = XXH3State::new();
+    let mut offset = 0;
+    let mut step = 1;
+    while offset < data.len() {
+        let end = (offset + step).min(data.len());
+        state.update(&data[offset..end]).unwrap();
+        offset = end;
+        step = step * 3 % 8191 + 1;
+    }
+    assert_eq!(state.digest_64(), xxh3_64bits(&data));
+    assert_eq!(state.digest_128(), xxh3_128bits(&data));
+}
+
+#[test]
+fn test_xxh3_seeded_long_uses_derived_secret() {
+    // Beyond 240 bytes, a seeded hash is the hash with the seed-derived secret
+    let secret = generate_secret_from_seed(TEST_SEED);
+    for &len in &[241, 1024, 4096] {
+        let data = fill_test_buffer(len);
+        assert_eq!(xxh3_64bits_with_secret(&data, &secret).unwrap(), xxh3_64bits_with_seed(&data, TEST_SEED));
+        assert_eq!(xxh3_128bits_with_secret(&data, &secret).unwrap(), xxh3_128bits_with_seed(&data, TEST_SEED));
+    }
+}
+
+#[test]
+fn test_generate_secret_vectors() {
+    let uuid: Vec<u8> = (0..16).collect();
+    let blob = fill_test_buffer(1000);
+    let short_blob = fill_test_buffer(7);
+
+    // (seed material, secret size, XXH3_64 of the secret generated by C XXH3_generateSecret)
+    let cases: &[(&[u8], usize, u64)] = &[
+        (b"", 136, 0xa2a8d2c5ce1add39),
+        (b"", 192, 0x88e42c85566530c4),
+        (b"tenant-42", 136, 0x4d93a8fd10585c8b),
+        (b"tenant-42", 192, 0x0b21c6a89aa65c3d),
+        (&uuid, 200, 0x23b16f491293e932),
+        (&blob, 1024, 0xa74a6573fb29b1ca),
+        (&short_blob, 4096, 0x33e6cf2575dae4d5),
+    ];
+
+    for &(seed_material, size, expected) in cases {
+        let mut secret = vec![0u8; size];
+        generate_secret(&mut secret, seed_material).unwrap();
+        assert_eq!(xxh3_64bits(&secret), expected, "seed len {} size {}", seed_material.len(), size);
+    }
+}
+
+#[test]
+fn test_generate_secret_too_small() {
+    let mut secret = [0u8; XXH3_SECRET_SIZE_MIN - 1];
+    assert_eq!(
+        generate_secret(&mut secret, b"tenant-42"),
+        Err(XXHashError::InvalidSecretSize(XXH3_SECRET_SIZE_MIN - 1))
+    );
+}
+
+/// (secret size, length, XXH3_64 and XXH3_128 with that secret and TEST_SEED)
+const XXH3_SECRET_AND_SEED: &[(usize, usize, u64, u128)] = &[
+    (147, 0, 0xa8a6b918b2f0364a, 0x00feaa732a3ce25ea986dfc5d7605bfe),
+    (147, 3, 0x634b8990b4976373, 0x1c7ecf6a308cf00e634b8990b4976373),
+    (147, 16, 0x663f29333b4db6b1, 0x6ffcb80cd33085c80346d13a7a5498c7),
+    (147, 100, 0xea932549a3d7fb01, 0x984cec52a9a9a561d5e0c2a715f11657),
+    (147, 240, 0xcc0f58c27ef3d8ee, 0x29d2133d6ea58c5b604e98db085c1864),
+    (147, 241, 0x454805371df98a91, 0x0ecde988107f17f2454805371df98a91),
+    (147, 1024, 0x3538a2d1ea7410d0, 0x7663338d0b32666d3538a2d1ea7410d0),
+    (147, 5000, 0x497f16c1335cccb0, 0xac7136126fa26aad497f16c1335cccb0),
+    (256, 0, 0xa8a6b918b2f0364a, 0x00feaa732a3ce25ea986dfc5d7605bfe),
+    (256, 3, 0x634b8990b4976373, 0x1c7ecf6a308cf00e634b8990b4976373),
+    (256, 16, 0x663f29333b4db6b1, 0x6ffcb80cd33085c80346d13a7a5498c7),
+    (256, 100, 0xea932549a3d7fb01, 0x984cec52a9a9a561d5e0c2a715f11657),
+    (256, 240, 0xcc0f58c27ef3d8ee, 0x29d2133d6ea58c5b604e98db085c1864),
+    (256, 241, 0x8ba89517adbc53c4, 0x2c2192793172c07d8ba89517adbc53c4),
+    (256, 1024, 0xdca11165eac14533, 0x39da4fd166265ef1dca11165eac14533),
+    (256, 5000, 0x741506aa4756ce87, 0x273d6294ab60a76e741506aa4756ce87),
+];
+
+#[test]
+fn test_xxh3_secret_and_seed_vectors() {
+    for &(secret_size, len, expected_64, expected_128) in XXH3_SECRET_AND_SEED {
+        let secret = test_secret(secret_size);
+        let data = fill_test_buffer(len);
+        let actual_64 = xxh3_64bits_with_secret_and_seed(&data, &secret, TEST_SEED).unwrap();
+        let actual_128 = xxh3_128bits_with_secret_and_seed(&data, &secret, TEST_SEED).unwrap();
+        assert_eq!(actual_64, expected_64, "secret {} len {}", secret_size, len);
+        assert_eq!(u128::from(actual_128), expected_128, "secret {} len {}", secret_size, len);
+    }
+}
+
+#[test]
+fn test_xxh3_secret_and_seed_streaming() {
+    let data = fill_test_buffer(3000);
+    let secret = test_secret(256);
+    for &seed in &[0, TEST_SEED] {
+        for &len in &[0, 100, 240, 241, 1024, 3000] {
+            let input = &data[..len];
+            let mut state = XXH3State::new_with_secret_and_seed(&secret, seed).unwrap();
+            update_in_chunks(&mut state, input, 97);
+            assert_eq!(
+                state.digest_64(),
+                xxh3_64bits_with_secret_and_seed(input, &secret, seed).unwrap(),
+                "seed {:x} len {}", seed, len
+            );
+            assert_eq!(
+                state.digest_128(),
+                xxh3_128bits_with_secret_and_seed(input, &secret, seed).unwrap(),
+                "seed {:x} len {}", seed, len
+            );
+        }
+    }
+
+    let mut state = XXH3State::new();
+    state.update(&data).unwrap();
+    state.reset_with_secret_and_seed(&secret, TEST_SEED).unwrap();
+    state.update(&data[..10]).unwrap();
+    assert_eq!(state.digest_64(), xxh3_64bits_with_seed(&data[..10], TEST_SEED));
+}
+
+#[test]
+fn test_xxh3_secret_and_seed_rejects_small_secret() {
+    let secret = [0u8; XXH3_SECRET_SIZE_MIN - 1];
+    let expected = XXHashError::InvalidSecretSize(XXH3_SECRET_SIZE_MIN - 1);
+    assert_eq!(xxh3_64bits_with_secret_and_seed(b"abc", &secret, 1), Err(expected.clone()));
+    assert_eq!(xxh3_128bits_with_secret_and_seed(b"abc", &secret, 1), Err(expected.clone()));
+    assert!(XXH3State::new_with_secret_and_seed(&secret, 1).is_err());
+    assert_eq!(XXH3State::new().reset_with_secret_and_seed(&secret, 1), Err(expected));
+}
+
+/// (secret size, length, XXH3_64 and XXH3_128 with that secret), around each size's block boundaries
+const XXH3_SECRET_SIZES: &[(usize, usize, u64, u128)] = &[
+    (136, 200, 0x6ced3a686ddeb0bf, 0x8ecb565d80b81f295e1ae1c5869d3dc8),
+    (136, 241, 0xce4f617e74e2747e, 0x35710269535b45d2ce4f617e74e2747e),
+    (136, 576, 0x9446862e224cdbe0, 0x375083f07fd8dd6e9446862e224cdbe0),
+    (136, 577, 0xe86ede03fd93d3d6, 0xf1ab6776bc40e803e86ede03fd93d3d6),
+    (136, 1215, 0xc7c39feaa00551ec, 0x3625d62dbf89c544c7c39feaa00551ec),
+    (136, 10000, 0x16227291a40d3bc0, 0x5da3891e47aab6fc16227291a40d3bc0),
+    (137, 200, 0x6ced3a686ddeb0bf, 0x8ecb565d80b81f295e1ae1c5869d3dc8),
+    (137, 241, 0x94aa2ad3d2024c39, 0x9fa5fa1335497ddc94aa2ad3d2024c39),
+    (137, 576, 0x603dbdc35c2125a6, 0xc6284f4474129d5a603dbdc35c2125a6),
+    (137, 577, 0x5fdd483d27acb418, 0x2e4edf14b0575d565fdd483d27acb418),
+    (137, 1215, 0x1534bc8f3d26051f, 0xd705db45331656b11534bc8f3d26051f),
+    (137, 10000, 0xa9765eb3fd42e4dd, 0xef4a9171a67ba30aa9765eb3fd42e4dd),
+    (199, 200, 0x6ced3a686ddeb0bf, 0x8ecb565d80b81f295e1ae1c5869d3dc8),
+    (199, 241, 0xf435f1a8a0397025, 0xa8e5b15822caa2a1f435f1a8a0397025),
+    (199, 1024, 0xb16c9be4e7c2a11b, 0x73b4b2c6c848b1f8b16c9be4e7c2a11b),
+    (199, 1025, 0x4d08ce49acba797d, 0x64ea456770abde384d08ce49acba797d),
+    (199, 2111, 0xf57d9de034ec5b67, 0xc132b855a0f9763af57d9de034ec5b67),
+    (199, 10000, 0x9f2648ec13c7f418, 0xc7d10afbacbb1daf9f2648ec13c7f418),
+    (256, 200, 0x6ced3a686ddeb0bf, 0x8ecb565d80b81f295e1ae1c5869d3dc8),
+    (256, 241, 0x8ba89517adbc53c4, 0x2c2192793172c07d8ba89517adbc53c4),
+    (256, 1536, 0x0c8788d33c885708, 0x41e9eab14b367d5f0c8788d33c885708),
+    (256, 1537, 0xe84212a1fe856809, 0xf23793128f8dacf3e84212a1fe856809),
+    (256, 3135, 0x9a013d1a892c836c, 0x18fc199d7e194fa59a013d1a892c836c),
+    (256, 10000, 0x4194e26e6bcaa41a, 0x1e100f77c85dc50e4194e26e6bcaa41a),
+    (1024, 200, 0x6ced3a686ddeb0bf, 0x8ecb565d80b81f295e1ae1c5869d3dc8),
+    (1024, 241, 0x7bf7c4b039ab5d66, 0xc3d78b69b55078787bf7c4b039ab5d66),
+    (1024, 7680, 0x9cfa55ff725f9c46, 0x774ba015b08d43ce9cfa55ff725f9c46),
+    (1024, 7681, 0x1ed0cce8df4712be, 0xdb9aafe2672e440d1ed0cce8df4712be),
+    (1024, 10000, 0xeaeeb637438ef00e, 0x4ad3301cc29ff917eaeeb637438ef00e),
+    (1024, 15423, 0xfd199119e4511d7c, 0xfbb7ae82a036426ffd199119e4511d7c),
+    (4096, 200, 0x6ced3a686ddeb0bf, 0x8ecb565d80b81f295e1ae1c5869d3dc8),
+    (4096, 241, 0x444534c48815c4df, 0x9cb2129fc8fefe12444534c48815c4df),
+    (4096, 10000, 0x1e198bbb28ffbcf9, 0x3c58729f669003c41e198bbb28ffbcf9),
+    (4096, 32256, 0x41769514a0b155a0, 0x2466200375e47c3041769514a0b155a0),
+    (4096, 32257, 0xb05abe2230ac78ad, 0xc1f688744ffb8702b05abe2230ac78ad),
+    (4096, 64575, 0xdd57a3827bd6a0af, 0xdb773b49ac0bacbcdd57a3827bd6a0af),
+    (4099, 200, 0x6ced3a686ddeb0bf, 0x8ecb565d80b81f295e1ae1c5869d3dc8),
+    (4099, 241, 0x57504b7452744de3, 0xc8681574b1bd1b9157504b7452744de3),
+    (4099, 10000, 0xe5d16524ddc302a0, 0xdf8c722399e2641fe5d16524ddc302a0),
+    (4099, 32256, 0x2288562154f9926d, 0x7c1e2d6d368d98912288562154f9926d),
+    (4099, 32257, 0x75ada65ffb1ccc0c, 0x5342835ca97bdc0475ada65ffb1ccc0c),
+    (4099, 64575, 0x5c6f814bc4a9322b, 0xcfa472b7464309145c6f814bc4a9322b),
+];
+
+#[test]
+fn test_xxh3_arbitrary_secret_sizes() {
+    for &(secret_size, len, expected_64, expected_128) in XXH3_SECRET_SIZES {
+        let secret = test_secret(secret_size);
+        let data = fill_test_buffer(len);
+        assert_eq!(xxh3_64bits_with_secret(&data, &secret).unwrap(), expected_64, "secret {} len {}", secret_size, len);
+        assert_eq!(
+            u128::from(xxh3_128bits_with_secret(&data, &secret).unwrap()),
+            expected_128,
+            "secret {} len {}", secret_size, len
+        );
+
+        // Without alloc, streaming states only hold secrets up to XXH3_SECRET_INLINE_MAX bytes
+        if !cfg!(feature = "alloc") && secret_size > XXH3_SECRET_INLINE_MAX {
+            assert_eq!(XXH3State::new_with_secret(&secret).err(), Some(XXHashError::InvalidSecretSize(secret_size)));
+            continue;
+        }
+        let mut state = XXH3State::new_with_secret(&secret).unwrap();
+        update_in_chunks(&mut state, &data, 333);
+        assert_eq!(state.digest_64(), expected_64, "streaming secret {} len {}", secret_size, len);
+        assert_eq!(u128::from(state.digest_128()), expected_128, "streaming secret {} len {}", secret_size, len);
+    }
+}